@@ -22,11 +22,8 @@ fn criterion_benchmark(c: &mut Criterion) {
     cape::skia::shaper::icu::init();
 
     let mut resources = cape::node::Resources {
-        fonts: Default::default(),
         fallback_text_size: 13.,
-        fallback_text_fill: cape::node::Paint::Solid(cape::Color::new(1., 1., 1., 1.)),
-        shaper_cache: Default::default(),
-        font_cache: Default::default(),
+        ..Default::default()
     };
 
     resources
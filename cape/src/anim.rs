@@ -0,0 +1,343 @@
+use crate::{Color, Point2, Rect, Size2};
+use std::time::{Duration, Instant};
+
+/// Distance and speed below which a spring is considered to be at rest.
+const SPRING_REST: f32 = 1e-3;
+/// Step size of the spring integration, in seconds.
+const SPRING_STEP: f32 = 1. / 240.;
+/// Longest stretch of time a spring is advanced by in one frame, so that a stalled frame doesn't stall the event loop too.
+const SPRING_MAX_DELTA: f32 = 0.1;
+
+/// A value which can be animated with `Cx::animate`.
+///
+/// Values are interpolated component-wise, with up to four components.
+pub trait Animatable: Copy + PartialEq + 'static {
+    fn to_components(self) -> [f32; 4];
+    fn from_components(components: [f32; 4]) -> Self;
+}
+
+impl Animatable for f32 {
+    fn to_components(self) -> [f32; 4] {
+        [self, 0., 0., 0.]
+    }
+
+    fn from_components(c: [f32; 4]) -> Self {
+        c[0]
+    }
+}
+
+impl Animatable for Point2 {
+    fn to_components(self) -> [f32; 4] {
+        [self.x, self.y, 0., 0.]
+    }
+
+    fn from_components(c: [f32; 4]) -> Self {
+        Point2::new(c[0], c[1])
+    }
+}
+
+impl Animatable for Size2 {
+    fn to_components(self) -> [f32; 4] {
+        [self.width, self.height, 0., 0.]
+    }
+
+    fn from_components(c: [f32; 4]) -> Self {
+        Size2::new(c[0], c[1])
+    }
+}
+
+impl Animatable for Color {
+    fn to_components(self) -> [f32; 4] {
+        [self.red, self.green, self.blue, self.alpha]
+    }
+
+    fn from_components(c: [f32; 4]) -> Self {
+        Color::new(c[0], c[1], c[2], c[3])
+    }
+}
+
+impl Animatable for Rect {
+    fn to_components(self) -> [f32; 4] {
+        [
+            self.origin.x,
+            self.origin.y,
+            self.size.width,
+            self.size.height,
+        ]
+    }
+
+    fn from_components(c: [f32; 4]) -> Self {
+        Rect::new(Point2::new(c[0], c[1]), Size2::new(c[2], c[3]))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    /// CSS-style cubic bézier curve from `(0, 0)` to `(1, 1)`, with the control points `(x1, y1)` and `(x2, y2)`.
+    CubicBezier(f32, f32, f32, f32),
+}
+
+impl Default for Easing {
+    fn default() -> Self {
+        Easing::EaseInOut
+    }
+}
+
+impl Easing {
+    /// Maps linear progress `t` (from 0 to 1) to eased progress.
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0., 1.);
+        match *self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1. - (1. - t).powi(3),
+            Easing::EaseInOut if t < 0.5 => 4. * t * t * t,
+            Easing::EaseInOut => 1. - (2. - 2. * t).powi(3) / 2.,
+            Easing::CubicBezier(x1, y1, x2, y2) => {
+                let bezier =
+                    |a: f32, b: f32, s: f32| 3. * s * (1. - s) * ((1. - s) * a + s * b) + s * s * s;
+
+                // x is monotonic in the curve parameter, so bisect for the point at which x = t
+                let (mut lo, mut hi) = (0., 1.);
+                for _ in 0..24 {
+                    let mid = (lo + hi) / 2.;
+                    if bezier(x1, x2, mid) < t {
+                        lo = mid;
+                    } else {
+                        hi = mid;
+                    }
+                }
+
+                bezier(y1, y2, (lo + hi) / 2.)
+            }
+        }
+    }
+}
+
+/// Describes how `Cx::animate` moves towards its target.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnimationSpec {
+    /// Eases from the value at the time the target was set to the target, over a fixed duration.
+    Tween { duration: Duration, easing: Easing },
+    /// Damped spring physics; velocity is carried over when the target changes mid-flight.
+    Spring {
+        stiffness: f32,
+        damping: f32,
+        /// Clamped to the lightest mass which can be simulated stably, so that light (or massless) springs don't blow up.
+        mass: f32,
+    },
+}
+
+impl Default for AnimationSpec {
+    fn default() -> Self {
+        AnimationSpec::tween(Duration::from_millis(200), Easing::EaseInOut)
+    }
+}
+
+impl AnimationSpec {
+    pub fn tween(duration: Duration, easing: Easing) -> Self {
+        AnimationSpec::Tween { duration, easing }
+    }
+
+    /// A spring with unit mass. `damping` of `2 * stiffness.sqrt()` is critically damped; anything less will overshoot.
+    pub fn spring(stiffness: f32, damping: f32) -> Self {
+        AnimationSpec::Spring {
+            stiffness,
+            damping,
+            mass: 1.,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Animation {
+    from: [f32; 4],
+    value: [f32; 4],
+    velocity: [f32; 4],
+    target: [f32; 4],
+    start: Instant,
+    last: Instant,
+    running: bool,
+}
+
+impl Animation {
+    pub(crate) fn new(value: [f32; 4], now: Instant) -> Self {
+        Animation {
+            from: value,
+            value,
+            velocity: [0.; 4],
+            target: value,
+            start: now,
+            last: now,
+            running: false,
+        }
+    }
+
+    /// Advances the animation to `now`, restarting it from the current value if `target` has changed.
+    pub(crate) fn update(
+        &mut self,
+        target: [f32; 4],
+        spec: &AnimationSpec,
+        now: Instant,
+    ) -> [f32; 4] {
+        if target != self.target {
+            self.from = self.value;
+            self.target = target;
+            self.start = now;
+            self.last = now;
+            self.running = true;
+        }
+
+        if !self.running {
+            return self.value;
+        }
+
+        match *spec {
+            AnimationSpec::Tween { duration, easing } => {
+                let t = if duration == Duration::from_secs(0) {
+                    1.
+                } else {
+                    (now - self.start).as_secs_f32() / duration.as_secs_f32()
+                };
+
+                let eased = easing.apply(t);
+                for i in 0..4 {
+                    self.value[i] = self.from[i] + (self.target[i] - self.from[i]) * eased;
+                }
+
+                if t >= 1. {
+                    self.settle();
+                }
+            }
+            AnimationSpec::Spring {
+                stiffness,
+                damping,
+                mass,
+            } => {
+                // explicit integration overshoots (and diverges) once a single step moves the value further than the target
+                let min_mass =
+                    damping.abs() * SPRING_STEP + stiffness.abs() * SPRING_STEP * SPRING_STEP;
+                let mass = mass.max(min_mass).max(f32::EPSILON);
+                let mut delta = (now - self.last).as_secs_f32().min(SPRING_MAX_DELTA);
+                while delta > 0. {
+                    let step = delta.min(SPRING_STEP);
+                    for i in 0..4 {
+                        let force = -stiffness * (self.value[i] - self.target[i])
+                            - damping * self.velocity[i];
+                        self.velocity[i] += force / mass * step;
+                        self.value[i] += self.velocity[i] * step;
+                    }
+                    delta -= step;
+                }
+
+                let at_rest = (0..4).all(|i| {
+                    (self.value[i] - self.target[i]).abs() < SPRING_REST
+                        && self.velocity[i].abs() < SPRING_REST
+                });
+                if at_rest {
+                    self.settle();
+                }
+            }
+        }
+
+        self.last = now;
+        self.value
+    }
+
+    pub(crate) fn is_running(&self) -> bool {
+        self.running
+    }
+
+    fn settle(&mut self) {
+        self.value = self.target;
+        self.velocity = [0.; 4];
+        self.running = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EASINGS: [Easing; 5] = [
+        Easing::Linear,
+        Easing::EaseIn,
+        Easing::EaseOut,
+        Easing::EaseInOut,
+        Easing::CubicBezier(0.25, 0.1, 0.25, 1.),
+    ];
+
+    #[test]
+    fn easings_start_and_end_at_the_bounds() {
+        for easing in &EASINGS {
+            assert!(easing.apply(0.).abs() < 1e-4, "{:?}", easing);
+            assert!((easing.apply(1.) - 1.).abs() < 1e-4, "{:?}", easing);
+        }
+    }
+
+    #[test]
+    fn easings_clamp_progress() {
+        for easing in &EASINGS {
+            assert_eq!(easing.apply(-1.), easing.apply(0.), "{:?}", easing);
+            assert_eq!(easing.apply(2.), easing.apply(1.), "{:?}", easing);
+        }
+    }
+
+    #[test]
+    fn easings_are_monotonic() {
+        for easing in &EASINGS {
+            let mut prev = easing.apply(0.);
+            for i in 1..=100 {
+                let value = easing.apply(i as f32 / 100.);
+                assert!(value >= prev - 1e-4, "{:?} at {}", easing, i);
+                prev = value;
+            }
+        }
+    }
+
+    #[test]
+    fn ease_in_out_is_symmetric() {
+        assert!((Easing::EaseInOut.apply(0.5) - 0.5).abs() < 1e-6);
+        for i in 0..=50 {
+            let t = i as f32 / 100.;
+            let (a, b) = (Easing::EaseInOut.apply(t), Easing::EaseInOut.apply(1. - t));
+            assert!((a + b - 1.).abs() < 1e-5, "at {}", t);
+        }
+    }
+
+    #[test]
+    fn linear_cubic_bezier_is_linear() {
+        let easing = Easing::CubicBezier(1. / 3., 1. / 3., 2. / 3., 2. / 3.);
+        for i in 0..=10 {
+            let t = i as f32 / 10.;
+            assert!((easing.apply(t) - t).abs() < 1e-4, "at {}", t);
+        }
+    }
+
+    #[test]
+    fn massless_springs_settle() {
+        let spec = AnimationSpec::Spring {
+            stiffness: 100.,
+            damping: 20.,
+            mass: 0.,
+        };
+        let start = Instant::now();
+        let mut animation = Animation::new([0.; 4], start);
+        animation.update([1., 0., 0., 0.], &spec, start);
+
+        let mut value = [0.; 4];
+        for frame in 1..=240 {
+            value = animation.update(
+                [1., 0., 0., 0.],
+                &spec,
+                start + Duration::from_millis(16 * frame),
+            );
+            assert!(value[0].is_finite());
+        }
+        assert_eq!(value, [1., 0., 0., 0.]);
+    }
+}
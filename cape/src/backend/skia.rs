@@ -1,9 +1,12 @@
 use crate::{
     cx,
-    node::{Paint, ResolvedNode},
-    Point2, Rect, ToSkia,
+    node::{
+        span_ranges, Effect, Filter, Paint, ResolvedNode, Resources, ShaderUniform, Shadow, ZOrder,
+    },
+    Point2, Rect, Size2, ToSkia, Transform2,
 };
 use skulpin::skia_safe as sk;
+use std::ops::Range;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -16,13 +19,30 @@ pub enum Error {
     PaintConversion,
     #[error("error rendering image")]
     Image,
+    #[error("{0} does not support this paint")]
+    UnsupportedPaint(&'static str),
+    #[error("shader uniform \"{0}\" is missing or has the wrong size")]
+    ShaderUniform(String),
+    #[error(transparent)]
+    Resources(#[from] crate::Error),
+}
+
+lazy_static::lazy_static! {
+    /// Reference point of `ShaderUniform::Time`.
+    static ref EPOCH: std::time::Instant = std::time::Instant::now();
 }
 
 fn rect_offset(rect: Rect, offset: Point2) -> Point2 {
     rect.origin + Point2::new(offset.x * rect.size.width, offset.y * rect.size.height).to_vector()
 }
 
-fn convert_paint(p: &Paint, rect: Rect, stroke: Option<f32>) -> Result<sk::Paint, Error> {
+fn convert_paint(
+    cx: &mut cx::Cx,
+    resources: &mut Resources,
+    p: &Paint,
+    rect: Rect,
+    stroke: Option<f32>,
+) -> Result<sk::Paint, Error> {
     let mut paint = sk::Paint::default();
     paint.set_anti_alias(true);
     if let Some(stroke) = stroke {
@@ -31,30 +51,46 @@ fn convert_paint(p: &Paint, rect: Rect, stroke: Option<f32>) -> Result<sk::Paint
     } else {
         paint.set_style(sk::PaintStyle::Fill);
     }
+    // gradient transforms are about the origin of the shape
+    let local_matrix = |transform: &Option<Transform2>| {
+        transform.map(|transform| {
+            Transform2::translation(-rect.origin.x, -rect.origin.y)
+                .then(&transform)
+                .then_translate(rect.origin.to_vector())
+                .to_skia()
+        })
+    };
+    let colors = |stops: &[(f32, crate::Color)]| {
+        stops
+            .iter()
+            .map(|stop| stop.1.to_skia())
+            .collect::<Vec<_>>()
+    };
+    let positions =
+        |stops: &[(f32, crate::Color)]| stops.iter().map(|stop| stop.0).collect::<Vec<_>>();
+
     match p {
         Paint::Solid(color) => {
             paint.set_color(color.to_skia());
         }
-        Paint::LinearGradient { stops, begin, end } => {
+        Paint::LinearGradient {
+            stops,
+            begin,
+            end,
+            tile_mode,
+            transform,
+        } => {
             paint.set_shader(
                 sk::gradient_shader::linear(
                     (
                         convert_point(rect_offset(rect, *begin)),
                         convert_point(rect_offset(rect, *end)),
                     ),
-                    stops
-                        .iter()
-                        .map(|stop| stop.1.to_skia())
-                        .collect::<Vec<_>>()
-                        .as_slice(),
-                    stops
-                        .iter()
-                        .map(|stop| stop.0)
-                        .collect::<Vec<_>>()
-                        .as_slice(),
-                    sk::TileMode::default(),
-                    None,
+                    colors(stops).as_slice(),
+                    positions(stops).as_slice(),
+                    *tile_mode,
                     None,
+                    local_matrix(transform).as_ref(),
                 )
                 .ok_or(Error::PaintConversion)?,
             );
@@ -63,62 +99,301 @@ fn convert_paint(p: &Paint, rect: Rect, stroke: Option<f32>) -> Result<sk::Paint
             stops,
             center,
             radius,
+            tile_mode,
+            transform,
         } => {
             paint.set_shader(
                 sk::gradient_shader::radial(
                     convert_point(rect_offset(rect, *center)),
                     *radius,
-                    stops
-                        .iter()
-                        .map(|stop| stop.1.to_skia())
-                        .collect::<Vec<_>>()
-                        .as_slice(),
-                    stops
-                        .iter()
-                        .map(|stop| stop.0)
-                        .collect::<Vec<_>>()
-                        .as_slice(),
-                    sk::TileMode::default(),
+                    colors(stops).as_slice(),
+                    positions(stops).as_slice(),
+                    *tile_mode,
                     None,
+                    local_matrix(transform).as_ref(),
+                )
+                .ok_or(Error::PaintConversion)?,
+            );
+        }
+        Paint::SweepGradient {
+            stops,
+            center,
+            start_angle,
+            end_angle,
+            tile_mode,
+            transform,
+        } => {
+            paint.set_shader(
+                sk::gradient_shader::sweep(
+                    convert_point(rect_offset(rect, *center)),
+                    colors(stops).as_slice(),
+                    positions(stops).as_slice(),
+                    *tile_mode,
+                    (*start_angle, *end_angle),
                     None,
+                    local_matrix(transform).as_ref(),
                 )
                 .ok_or(Error::PaintConversion)?,
             );
         }
-        Paint::Image(img) => {
+        Paint::TwoPointConicalGradient {
+            stops,
+            start,
+            start_radius,
+            end,
+            end_radius,
+            tile_mode,
+            transform,
+        } => {
             paint.set_shader(
-                sk::Image::from_raster_data(
-                    &sk::ImageInfo::new(
-                        sk::ISize::new(img.width() as _, img.height() as _),
-                        sk::ColorType::RGBA8888,
-                        sk::AlphaType::Unpremul,
-                        None,
-                    ),
-                    unsafe { sk::Data::new_bytes(img.as_raw()) },
-                    4 * img.width() as usize,
+                sk::gradient_shader::two_point_conical(
+                    convert_point(rect_offset(rect, *start)),
+                    *start_radius,
+                    convert_point(rect_offset(rect, *end)),
+                    *end_radius,
+                    colors(stops).as_slice(),
+                    positions(stops).as_slice(),
+                    *tile_mode,
+                    None,
+                    local_matrix(transform).as_ref(),
                 )
-                .ok_or(Error::Image)?
-                .to_shader(None, None),
+                .ok_or(Error::PaintConversion)?,
             );
         }
-        _ => {}
+        Paint::Image(img) => {
+            paint.set_shader(resources.image(img)?.to_shader(None, None));
+        }
+        Paint::Shader { source, uniforms } => {
+            let (shader, animated) = convert_shader(resources, source, uniforms, rect)?;
+            if animated {
+                // keep drawing frames for as long as the shader is visible
+                cx.request_redraw();
+            }
+            paint.set_shader(shader);
+        }
+        Paint::Blur { .. } => return Err(Error::UnsupportedPaint("this shape")),
+        Paint::Composite {
+            paint: inner,
+            blend_mode,
+            opacity,
+        } => {
+            paint = convert_paint(cx, resources, inner, rect, stroke)?;
+            if let Some(blend_mode) = blend_mode {
+                paint.set_blend_mode(*blend_mode);
+            }
+            paint.set_alpha_f(paint.alpha_f() * opacity.clamp(0., 1.));
+        }
     }
     Ok(paint)
 }
 
+/// Strips the `Paint::Composite`s wrapping `paint`, returning the paint within and a layer paint with their blend mode and opacity.
+fn split_composite(mut paint: &Paint) -> (&Paint, sk::Paint) {
+    let mut layer = sk::Paint::default();
+    let mut outer_blend_mode = None;
+    while let Paint::Composite {
+        paint: inner,
+        blend_mode,
+        opacity,
+    } = paint
+    {
+        // as in `convert_paint`, outer blend modes replace inner ones
+        outer_blend_mode = outer_blend_mode.or(*blend_mode);
+        layer.set_alpha_f(layer.alpha_f() * opacity.clamp(0., 1.));
+        paint = inner.as_ref();
+    }
+    if let Some(blend_mode) = outer_blend_mode {
+        layer.set_blend_mode(blend_mode);
+    }
+    (paint, layer)
+}
+
+/// Builds the shader of a `Paint::Shader` painted over `rect`, and whether it depends on `ShaderUniform::Time`.
+fn convert_shader(
+    resources: &mut Resources,
+    source: &str,
+    uniforms: &[(String, ShaderUniform)],
+    rect: Rect,
+) -> Result<(sk::Shader, bool), Error> {
+    let mut effect = resources.shader(source)?;
+    let mut animated = false;
+
+    let mut data = vec![0u8; effect.uniform_size()];
+    for uniform in effect.uniforms() {
+        let value = uniforms
+            .iter()
+            .find(|(name, _)| name == uniform.name())
+            .map(|(_, value)| match value {
+                ShaderUniform::Float(value) => vec![*value],
+                ShaderUniform::Floats(values) => values.clone(),
+                ShaderUniform::Color(color) => {
+                    vec![color.red, color.green, color.blue, color.alpha]
+                }
+                ShaderUniform::Rect => vec![
+                    rect.origin.x,
+                    rect.origin.y,
+                    rect.size.width,
+                    rect.size.height,
+                ],
+                ShaderUniform::Time => {
+                    animated = true;
+                    vec![EPOCH.elapsed().as_secs_f32()]
+                }
+            })
+            .filter(|value| value.len() * 4 == uniform.size_in_bytes())
+            .ok_or_else(|| Error::ShaderUniform(uniform.name().to_owned()))?;
+
+        for (i, value) in value.iter().enumerate() {
+            let offset = uniform.offset() + i * 4;
+            data[offset..offset + 4].copy_from_slice(&value.to_ne_bytes());
+        }
+    }
+
+    let shader = effect
+        .make_shader(sk::Data::new_copy(&data), None, None, false)
+        .ok_or(Error::PaintConversion)?;
+    Ok((shader, animated))
+}
+
+fn convert_effect(effect: &Effect) -> Result<sk::Paint, Error> {
+    let mut paint = sk::Paint::default();
+    paint.set_alpha_f(effect.opacity.clamp(0., 1.));
+    if let Some(blend_mode) = effect.blend_mode {
+        paint.set_blend_mode(blend_mode);
+    }
+
+    let mut image_filter = None;
+    for filter in &effect.filters {
+        image_filter = Some(
+            match filter {
+                Filter::Blur { radius } => sk::image_filters::blur(
+                    (blur_sigma(*radius), blur_sigma(*radius)),
+                    sk::TileMode::Decal,
+                    image_filter,
+                    None,
+                ),
+                Filter::DropShadow {
+                    offset,
+                    radius,
+                    color,
+                } => sk::image_filters::drop_shadow(
+                    convert_point(*offset),
+                    (blur_sigma(*radius), blur_sigma(*radius)),
+                    color.to_skia(),
+                    image_filter,
+                    None,
+                ),
+                Filter::ColorMatrix(matrix) => sk::image_filters::color_filter(
+                    sk::color_filters::matrix_row_major(matrix),
+                    image_filter,
+                    None,
+                ),
+            }
+            .ok_or(Error::PaintConversion)?,
+        );
+    }
+    paint.set_image_filter(image_filter);
+
+    Ok(paint)
+}
+
+/// Converts a blur radius to the standard deviation Skia expects, as in CSS.
+fn blur_sigma(radius: f32) -> f32 {
+    radius / 2.
+}
+
 fn convert_point(point: Point2) -> sk::Point {
     sk::Point::new(point.x, point.y)
 }
 
+fn convert_rrect(rect: Rect, corner_radii: &[f32; 4]) -> sk::RRect {
+    sk::RRect::new_rect_radii(
+        rect.to_skia(),
+        &[
+            sk::Vector::new(corner_radii[0], corner_radii[0]),
+            sk::Vector::new(corner_radii[1], corner_radii[1]),
+            sk::Vector::new(corner_radii[2], corner_radii[2]),
+            sk::Vector::new(corner_radii[3], corner_radii[3]),
+        ],
+    )
+}
+
+fn render_shadow(
+    cx: &mut cx::Cx,
+    resources: &mut Resources,
+    canvas: &mut sk::Canvas,
+    rect: Rect,
+    corner_radii: &[f32; 4],
+    shadow: &Shadow,
+) -> Result<(), Error> {
+    let mut paint = convert_paint(cx, resources, &Paint::Solid(shadow.color), rect, None)?;
+    if shadow.blur > 0. {
+        paint.set_mask_filter(
+            sk::MaskFilter::blur(sk::BlurStyle::Normal, blur_sigma(shadow.blur), None)
+                .ok_or(Error::PaintConversion)?,
+        );
+    }
+
+    let rrect = convert_rrect(rect, corner_radii);
+    // grows (or shrinks, for negative `delta`) the rectangle along with its corners
+    let spread = |delta: f32| {
+        let radii = [
+            (corner_radii[0] + delta).max(0.),
+            (corner_radii[1] + delta).max(0.),
+            (corner_radii[2] + delta).max(0.),
+            (corner_radii[3] + delta).max(0.),
+        ];
+        convert_rrect(
+            rect.inflate(delta, delta)
+                .translate(shadow.offset.to_vector()),
+            &radii,
+        )
+    };
+
+    canvas.save();
+    if shadow.inset {
+        canvas.clip_rrect(rrect, None, true);
+
+        // the shadow is cast by everything outside of the (shrunk) rectangle
+        let margin = shadow.blur * 2.
+            + shadow.spread.abs()
+            + shadow.offset.x.abs().max(shadow.offset.y.abs());
+        canvas.draw_drrect(
+            convert_rrect(rect.inflate(margin, margin), corner_radii),
+            spread(-shadow.spread),
+            &paint,
+        );
+    } else {
+        canvas.clip_rrect(rrect, sk::ClipOp::Difference, true);
+        canvas.draw_rrect(spread(shadow.spread), &paint);
+    }
+    canvas.restore();
+
+    Ok(())
+}
+
+/// Returns the area painted by `node` itself, which outer shadows extend beyond its rectangle.
+fn painted_rect(node: &ResolvedNode) -> Rect {
+    match node {
+        ResolvedNode::Rectangle { rect, shadows, .. } => {
+            shadows.iter().fold(*rect, |painted, shadow| {
+                painted.union(&shadow.bounds(*rect))
+            })
+        }
+        _ => node.rect(),
+    }
+}
+
 pub fn render_list(
     cx: &mut cx::Cx,
+    resources: &mut Resources,
     canvas: &mut sk::Canvas,
     list: &[ResolvedNode],
     cull: &Rect,
 ) -> Result<(), Error> {
     for node in list {
-        if cull.intersects(&node.rect()) {
-            render_node(cx, canvas, node)?;
+        if cull.intersects(&painted_rect(node)) {
+            render_node(cx, resources, canvas, node)?;
         }
     }
     Ok(())
@@ -126,23 +401,197 @@ pub fn render_list(
 
 pub fn render_tree(
     cx: &mut cx::Cx,
+    resources: &mut Resources,
+    canvas: &mut sk::Canvas,
+    node: &ResolvedNode,
+    cull: &Rect,
+) -> Result<(), Error> {
+    for (layer, transform) in node.layers() {
+        // a degenerate transform means the layer has no visible area
+        if let Some(inverse) = transform.inverse() {
+            canvas.save();
+            canvas.concat(&transform.to_skia());
+            let result = render_layer(
+                cx,
+                resources,
+                canvas,
+                layer,
+                &inverse.outer_transformed_rect(cull),
+            );
+            canvas.restore();
+            result?;
+        }
+    }
+
+    Ok(())
+}
+
+fn render_layer(
+    cx: &mut cx::Cx,
+    resources: &mut Resources,
+    canvas: &mut sk::Canvas,
+    node: &ResolvedNode,
+    cull: &Rect,
+) -> Result<(), Error> {
+    let transform = node.local_transform();
+
+    // move the cull rect into the space of this node's content
+    let cull = match transform.map(|transform| transform.inverse()) {
+        Some(Some(inverse)) => inverse.outer_transformed_rect(cull),
+        Some(None) => return Ok(()),
+        None => *cull,
+    };
+
+    if cull.intersects(&painted_rect(node)) {
+        let save_count = if let Some(transform) = transform {
+            let save_count = canvas.save();
+            canvas.concat(&transform.to_skia());
+            Some(save_count)
+        } else if let ResolvedNode::Clip {
+            rect, corner_radii, ..
+        } = node
+        {
+            let save_count = canvas.save();
+            canvas.clip_rrect(convert_rrect(*rect, corner_radii), None, true);
+            Some(save_count)
+        } else if let ResolvedNode::Effect { effect, .. } = node {
+            if effect.is_noop() {
+                None
+            } else {
+                let paint = convert_effect(effect)?;
+                Some(canvas.save_layer(&sk::canvas::SaveLayerRec::default().paint(&paint)))
+            }
+        } else {
+            None
+        };
+
+        // transitions fade in and out on top of any movement
+        let save_count = match node {
+            ResolvedNode::Transition { state, .. } if state.opacity() < 1. => {
+                let layer = canvas.save_layer_alpha(None, (state.opacity() * 255.) as _);
+                save_count.or(Some(layer))
+            }
+            _ => save_count,
+        };
+
+        // the canvas is restored even if rendering fails, so that the error can't leave it transformed
+        let result = render_contents(cx, resources, canvas, node, &cull);
+
+        if let Some(save_count) = save_count {
+            canvas.restore_to_count(save_count);
+        }
+
+        result?;
+    }
+
+    Ok(())
+}
+
+/// Renders `node` along with its children, ordered by `ZOrder::Below`.
+fn render_contents(
+    cx: &mut cx::Cx,
+    resources: &mut Resources,
     canvas: &mut sk::Canvas,
     node: &ResolvedNode,
     cull: &Rect,
 ) -> Result<(), Error> {
-    if cull.intersects(&node.rect()) {
-        render_node(cx, canvas, node)?;
+    let children = node.paint_children();
+    let below = children
+        .iter()
+        .take_while(|child| child.z_order() == ZOrder::Below)
+        .count();
+
+    for child in &children[..below] {
+        render_layer(cx, resources, canvas, child, cull)?;
+    }
+
+    render_node(cx, resources, canvas, node)?;
+
+    for child in &children[below..] {
+        render_layer(cx, resources, canvas, child, cull)?;
+    }
+
+    Ok(())
+}
+
+/// Paints `paragraph` at `rect`, where `spans` are the (UTF-16) ranges of its text with their fill and background.
+///
+/// Spans with a fill other than `Paint::Solid` were shaped in white, and are painted over by their fill here.
+fn render_paragraph(
+    cx: &mut cx::Cx,
+    resources: &mut Resources,
+    canvas: &mut sk::Canvas,
+    paragraph: &sk::textlayout::Paragraph,
+    rect: Rect,
+    spans: &[(Range<usize>, &Paint, Option<&Paint>)],
+) -> Result<(), Error> {
+    let text_boxes = |range: &Range<usize>| {
+        paragraph
+            .get_rects_for_range(
+                range.clone(),
+                sk::textlayout::RectHeightStyle::Max,
+                sk::textlayout::RectWidthStyle::Tight,
+            )
+            .iter()
+            .map(|text_box| text_box.rect.with_offset(convert_point(rect.origin)))
+            .collect::<Vec<_>>()
+    };
+
+    for (range, fill, background) in spans {
+        if let Paint::Blur { .. } = fill {
+            return Err(Error::UnsupportedPaint("text"));
+        }
+
+        if let Some(background) = background {
+            let paint = convert_paint(cx, resources, background, rect, None)?;
+            for text_box in text_boxes(range) {
+                canvas.draw_rect(text_box, &paint);
+            }
+        }
+    }
+
+    // paints are converted up front, so that an error can't leave layers unrestored
+    let masked = spans
+        .iter()
+        .filter(|(_, fill, _)| !matches!(fill, Paint::Solid(_)))
+        .map(|(range, fill, _)| Ok((range, convert_paint(cx, resources, fill, rect, None)?)))
+        .collect::<Result<Vec<_>, Error>>()?;
+    if masked.is_empty() {
+        paragraph.paint(canvas, convert_point(rect.origin));
+        return Ok(());
+    }
+
+    let bounds = rect.to_skia();
+    let mut mask = sk::Paint::default();
+    mask.set_blend_mode(sk::BlendMode::SrcIn);
 
-        for child in node.children() {
-            render_tree(cx, canvas, child, cull)?;
+    canvas.save_layer(&sk::canvas::SaveLayerRec::default().bounds(&bounds));
+    paragraph.paint(canvas, convert_point(rect.origin));
+    for (range, fill) in masked {
+        let mut clip = sk::Path::new();
+        for text_box in text_boxes(range) {
+            clip.add_rect(text_box, None);
         }
+
+        canvas.save();
+        canvas.clip_path(&clip, None, true);
+        canvas.save_layer(
+            &sk::canvas::SaveLayerRec::default()
+                .bounds(&bounds)
+                .paint(&mask),
+        );
+        canvas.draw_rect(bounds, &fill);
+        canvas.restore();
+        canvas.restore();
     }
+    canvas.restore();
 
     Ok(())
 }
 
 pub fn render_node(
     cx: &mut cx::Cx,
+    resources: &mut Resources,
     canvas: &mut sk::Canvas,
     node: &ResolvedNode,
 ) -> Result<(), Error> {
@@ -151,88 +600,247 @@ pub fn render_node(
             fill, rect, blob, ..
         } => {
             if let Paint::Blur { .. } = fill {
-                panic!("text does not support blur paint");
+                return Err(Error::UnsupportedPaint("text"));
             }
 
             if let Some(blob) = blob {
                 canvas.draw_text_blob(
                     blob,
                     convert_point(rect.origin),
-                    &convert_paint(fill, node.rect(), None)?,
+                    &convert_paint(cx, resources, fill, node.rect(), None)?,
                 );
             }
         }
+        ResolvedNode::Paragraph {
+            text,
+            fill,
+            paragraph,
+            rect,
+            ..
+        } => {
+            let spans = [(0..text.encode_utf16().count(), fill, None)];
+            render_paragraph(cx, resources, canvas, &paragraph.borrow(), *rect, &spans)?;
+        }
+        ResolvedNode::RichText {
+            spans,
+            paragraph,
+            rect,
+            ..
+        } => {
+            let fallback_fill = resources.fallback_text_fill.clone();
+            let spans = spans
+                .iter()
+                .zip(span_ranges(spans))
+                .map(|(span, range)| {
+                    (
+                        range,
+                        span.fill.as_ref().unwrap_or(&fallback_fill),
+                        span.background.as_ref(),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            render_paragraph(cx, resources, canvas, &paragraph.borrow(), *rect, &spans)?;
+        }
         ResolvedNode::Rectangle {
             rect,
             corner_radii,
             background,
             border,
             border_fill,
-            ..
+            shadows,
         } => {
-            if let Some(bg) = background {
-                let rrect = sk::RRect::new_rect_radii(
-                    rect.to_skia(),
-                    &[
-                        sk::Vector::new(corner_radii[0], corner_radii[0]),
-                        sk::Vector::new(corner_radii[1], corner_radii[1]),
-                        sk::Vector::new(corner_radii[2], corner_radii[2]),
-                        sk::Vector::new(corner_radii[3], corner_radii[3]),
-                    ],
-                );
-
-                match bg {
-                    Paint::Blur { radius, tint } => {
-                        canvas.save();
+            for shadow in shadows.iter().filter(|shadow| !shadow.inset) {
+                render_shadow(cx, resources, canvas, *rect, corner_radii, shadow)?;
+            }
 
-                        canvas.clip_rrect(rrect, None, true);
+            if let Some(bg) = background {
+                let rrect = convert_rrect(*rect, corner_radii);
 
+                match split_composite(bg) {
+                    (Paint::Blur { radius, tint }, layer) => {
+                        let sigma = blur_sigma(*radius);
                         let blur = sk::image_filters::blur(
-                            (*radius, *radius),
+                            (sigma, sigma),
                             sk::TileMode::Clamp,
                             None,
                             &rect.to_skia().round(),
                         )
                         .ok_or(Error::PaintConversion)?;
+                        let tint = convert_paint(cx, resources, &Paint::Solid(*tint), *rect, None)?;
 
-                        canvas.save_layer(&sk::canvas::SaveLayerRec::default().backdrop(&blur));
+                        canvas.save();
+
+                        canvas.clip_rrect(rrect, None, true);
 
-                        canvas.draw_rect(
-                            rect.to_skia(),
-                            &convert_paint(&Paint::Solid(*tint), *rect, None)?,
+                        // the blend mode and opacity of a composite apply to the blurred backdrop as a whole
+                        canvas.save_layer(
+                            &sk::canvas::SaveLayerRec::default()
+                                .backdrop(&blur)
+                                .paint(&layer),
                         );
 
+                        canvas.draw_rect(rect.to_skia(), &tint);
+
                         canvas.restore();
                         canvas.restore();
                     }
                     _ => {
-                        canvas.draw_rrect(rrect, &convert_paint(bg, *rect, None)?);
+                        canvas.draw_rrect(rrect, &convert_paint(cx, resources, bg, *rect, None)?);
                     }
                 }
             }
 
+            for shadow in shadows.iter().filter(|shadow| shadow.inset) {
+                render_shadow(cx, resources, canvas, *rect, corner_radii, shadow)?;
+            }
+
             if let Some(b) = border_fill {
                 if let Paint::Blur { .. } = b {
-                    panic!("border fill does not support blur paint");
+                    return Err(Error::UnsupportedPaint("border fill"));
                 }
 
                 canvas.draw_rrect(
-                    sk::RRect::new_rect_radii(
-                        rect.to_skia(),
-                        &[
-                            sk::Vector::new(corner_radii[0], corner_radii[0]),
-                            sk::Vector::new(corner_radii[1], corner_radii[1]),
-                            sk::Vector::new(corner_radii[2], corner_radii[2]),
-                            sk::Vector::new(corner_radii[3], corner_radii[3]),
-                        ],
-                    ),
-                    &convert_paint(b, *rect, Some(*border))?,
+                    convert_rrect(*rect, corner_radii),
+                    &convert_paint(cx, resources, b, *rect, Some(*border))?,
                 );
             }
         }
+        ResolvedNode::Path {
+            sk_path,
+            fill,
+            stroke,
+            rect,
+            ..
+        } => {
+            let local = Rect::new(Default::default(), rect.size);
+
+            let fill = fill
+                .as_ref()
+                .map(|fill| convert_paint(cx, resources, fill, local, None))
+                .transpose()?;
+            let stroke = stroke
+                .as_ref()
+                .map(|stroke| {
+                    let mut paint =
+                        convert_paint(cx, resources, &stroke.paint, local, Some(stroke.width))?;
+                    stroke.apply(&mut paint);
+                    Ok::<_, Error>(paint)
+                })
+                .transpose()?;
+
+            canvas.save();
+            canvas.translate(convert_point(rect.origin));
+
+            if let Some(fill) = fill {
+                canvas.draw_path(sk_path, &fill);
+            }
+
+            if let Some(stroke) = stroke {
+                canvas.draw_path(sk_path, &stroke);
+            }
+
+            canvas.restore();
+        }
+        ResolvedNode::Image {
+            sk_image,
+            fit,
+            align,
+            quality,
+            rect,
+            ..
+        } => {
+            let dst = fit.apply(
+                Size2::new(sk_image.width() as _, sk_image.height() as _),
+                *rect,
+                *align,
+            );
+
+            let mut paint = sk::Paint::default();
+            paint.set_filter_quality(*quality);
+
+            canvas.save();
+            canvas.clip_rect(rect.to_skia(), None, None);
+            canvas.draw_image_rect(sk_image, None, dst.to_skia(), &paint);
+            canvas.restore();
+        }
         ResolvedNode::Draw { rect, draw_fn, .. } => draw_fn(*rect, cx, canvas),
         _ => {}
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{point2, size2, Color};
+
+    #[test]
+    fn shaders_render_on_the_cpu() {
+        let mut resources = Resources::default();
+
+        // red ramps up across the rect, while green and blue come from `tint`
+        let source = "uniform float4 rect; uniform float4 tint; \
+            void main(float2 p, inout half4 color) { \
+                color = half4(half((p.x - rect.x) / rect.z), half(tint.g), half(tint.b), 1); \
+            }";
+        let uniforms = [
+            (String::from("rect"), ShaderUniform::Rect),
+            (
+                String::from("tint"),
+                ShaderUniform::Color(Color::new(0., 0.5, 1., 1.)),
+            ),
+        ];
+        let rect = Rect::new(point2(0., 0.), size2(4., 1.));
+        let (shader, animated) = convert_shader(&mut resources, source, &uniforms, rect).unwrap();
+        assert!(!animated);
+
+        let mut surface = sk::Surface::new_raster_n32_premul((4, 1)).unwrap();
+        let mut paint = sk::Paint::default();
+        paint.set_shader(shader);
+        surface
+            .canvas()
+            .draw_rect(sk::Rect::from_wh(4., 1.), &paint);
+
+        let info = sk::ImageInfo::new((4, 1), sk::ColorType::RGBA8888, sk::AlphaType::Premul, None);
+        let mut pixels = [0u8; 16];
+        assert!(surface.read_pixels(&info, &mut pixels, 16, (0, 0)));
+
+        // sampled at the centers of the pixels
+        let expected = [
+            [32, 128, 255, 255],
+            [96, 128, 255, 255],
+            [159, 128, 255, 255],
+            [223, 128, 255, 255],
+        ];
+        for (pixel, expected) in pixels.chunks(4).zip(&expected) {
+            for (&channel, &expected) in pixel.iter().zip(expected) {
+                assert!(
+                    (channel as i32 - expected).abs() <= 1,
+                    "{:?} != {:?}",
+                    pixel,
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn shaders_need_all_their_uniforms() {
+        let mut resources = Resources::default();
+
+        let source = "uniform float time; \
+            void main(float2 p, inout half4 color) { color = half4(half(fract(time)), 0, 0, 1); }";
+        let rect = Rect::new(point2(0., 0.), size2(4., 1.));
+
+        assert!(matches!(
+            convert_shader(&mut resources, source, &[], rect),
+            Err(Error::ShaderUniform(name)) if name == "time"
+        ));
+
+        let uniforms = [(String::from("time"), ShaderUniform::Time)];
+        let (_, animated) = convert_shader(&mut resources, source, &uniforms, rect).unwrap();
+        assert!(animated);
+    }
+}
@@ -2,23 +2,38 @@ use crate::{
     backend::skia::render_tree,
     cx,
     id::Id,
-    node::{Interaction, MouseButton, Node, Paint, ResolvedNode, Resources},
-    Color, Point2, Rect, Size2,
+    node::{BoxConstraints, Interaction, Key, MouseButton, Node, ResolvedNode, Resources, Stroke},
+    Color, Point2, Rect, Size2, Transform2,
 };
+use fxhash::FxHashMap;
+use ordered_float::OrderedFloat;
 use skulpin::winit;
-use std::{cmp::Ordering, rc::Rc};
+use std::{
+    rc::Rc,
+    time::{Duration, Instant},
+};
 use thiserror::Error;
 use winit::{
-    event::{ElementState, Event, WindowEvent},
+    event::{ElementState, Event, MouseScrollDelta, WindowEvent},
     event_loop::ControlFlow,
+    platform::run_return::EventLoopExtRunReturn,
 };
 
+/// Logical pixels scrolled per line reported by a mouse wheel.
+const SCROLL_LINE_HEIGHT: f32 = 20.;
+/// Shortest time between two frames, limiting the frame rate to 120 FPS.
+const FRAME_INTERVAL: Duration = Duration::from_millis(8);
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("os error within winit: {0}")]
     WinitOs(#[from] winit::error::OsError),
     #[error("failed to create a skulpin renderer: {0}")]
     CreateRenderer(#[from] skulpin::CreateRendererError),
+    #[error("failed to render: {0}")]
+    Render(#[from] crate::backend::skia::Error),
+    #[error("failed to resolve a node: {0}")]
+    Resolve(#[from] crate::Error),
 }
 
 pub struct Window {
@@ -34,14 +49,25 @@ pub struct WindowInfo {
 struct InteractNode {
     callback: Rc<dyn Fn(&mut cx::Cx, &Interaction)>,
     id: Id,
+    /// Maps from window space to the space of the node.
+    transform: Transform2,
 }
 
+impl InteractNode {
+    fn call(&self, cx: &mut cx::Cx, event: &Interaction) {
+        (*self.callback)(cx, &event.transformed(&self.transform));
+    }
+}
+
+/// Opens a window and runs the event loop until the window is closed, or until a frame fails to render.
+///
+/// Desktop only, since it returns once the event loop exits (see `EventLoopExtRunReturn`), which mobile and web platforms don't support.
 pub fn run(
     mut f: impl FnMut(&WindowInfo, &mut cx::Cx, &mut Resources) -> Window + 'static,
 ) -> Result<(), Error> {
     skulpin::skia_safe::icu::init();
 
-    let event_loop = winit::event_loop::EventLoop::new();
+    let mut event_loop = winit::event_loop::EventLoop::new();
 
     let logical_size = winit::dpi::LogicalSize::new(900., 600.);
 
@@ -58,13 +84,7 @@ pub fn run(
         .prefer_mailbox_present_mode()
         .build(&window)?;
 
-    let mut resources = Resources {
-        fonts: Default::default(),
-        fallback_text_size: 12.,
-        fallback_text_fill: Paint::Solid(Color::new(1., 1., 1., 1.)),
-        shaper_cache: Default::default(),
-        font_cache: Default::default(),
-    };
+    let mut resources = Resources::default();
 
     let mut cx = cx::Cx::new(event_loop.create_proxy());
 
@@ -81,12 +101,19 @@ pub fn run(
     let mut pressed_node: Option<InteractNode> = None;
     let mut focused_node: Option<InteractNode> = None;
 
-    let mut last_redraw = std::time::Instant::now();
+    let mut last_redraw = Instant::now();
+    // whether a frame needs to be drawn, either due to input or a request from `Cx`
+    let mut redraw_pending = true;
+    // the first error to occur, which ends the event loop
+    let mut error = None;
 
-    event_loop.run(move |event, _window_target, control_flow| {
+    event_loop.run_return(|event, _window_target, control_flow| {
         let window = skulpin::WinitWindow::new(&winit_window);
 
-        *control_flow = ControlFlow::Wait;
+        // any input (or a wakeup from `Cx::exec`) may have changed state
+        if let Event::WindowEvent { .. } | Event::UserEvent(()) = &event {
+            redraw_pending = true;
+        }
 
         match event {
             Event::WindowEvent {
@@ -124,16 +151,11 @@ pub fn run(
 
                 let prev_hovered = hovered_node.clone();
 
-                if let Some(ResolvedNode::Interact { callback, id, .. }) =
-                    node_at_point_tree(mouse_pos, &curr_node, None)
-                {
-                    hovered_node = Some(InteractNode {
-                        callback: Rc::clone(callback),
-                        id: *id,
-                    });
-                } else {
-                    hovered_node = None;
-                }
+                let mut path = Vec::new();
+                node_at_point(mouse_pos, &curr_node, &mut path);
+                hovered_node = path
+                    .last()
+                    .and_then(|(node, transform)| interact_node(node, *transform));
 
                 if !compare_interact(&prev_hovered, &hovered_node) {
                     try_callback(
@@ -185,19 +207,19 @@ pub fn run(
                         if let Some(node) = &hovered_node {
                             pressed_node = Some(node.clone());
                             focused_node = Some(node.clone());
-                            (*node.callback)(&mut cx, &event);
+                            node.call(&mut cx, &event);
                         }
                     }
                     ElementState::Pressed => {
                         if let Some(node) = &pressed_node {
-                            (*node.callback)(&mut cx, &event);
+                            node.call(&mut cx, &event);
                         }
                     }
                     ElementState::Released => {
                         if let Some(node) = pressed_node.clone() {
                             // FIXME(jazzfool): only make pressed_none = None if *all* mouse buttons have been released
                             pressed_node = None;
-                            (*node.callback)(&mut cx, &event);
+                            node.call(&mut cx, &event);
                         }
                     }
                 }
@@ -211,13 +233,44 @@ pub fn run(
                     try_callback(&focused_node, &mut cx, &Interaction::GainFocus);
                 }
             }
+            Event::WindowEvent {
+                event: WindowEvent::MouseWheel { delta, .. },
+                ..
+            } => {
+                let delta = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => {
+                        Point2::new(x * SCROLL_LINE_HEIGHT, y * SCROLL_LINE_HEIGHT)
+                    }
+                    MouseScrollDelta::PixelDelta(pos) => {
+                        let logical = pos.to_logical(scale_factor);
+                        Point2::new(logical.x, logical.y)
+                    }
+                };
+
+                let event = Interaction::Scroll {
+                    delta,
+                    pos: mouse_pos,
+                    modifiers,
+                };
+
+                // scrolling bubbles up from the innermost interact node, so that scrollable containers receive it even when hovering their content, until a handler stops it
+                let mut path = Vec::new();
+                node_at_point(mouse_pos, &curr_node, &mut path);
+                cx.take_stop_propagation();
+                for (node, transform) in path.into_iter().rev() {
+                    try_callback(&interact_node(node, transform), &mut cx, &event);
+                    if cx.take_stop_propagation() {
+                        break;
+                    }
+                }
+            }
             Event::WindowEvent {
                 event: WindowEvent::KeyboardInput { input, .. },
                 ..
             } => {
                 if let Some(node) = &focused_node {
                     if let Some(keycode) = input.virtual_keycode {
-                        (*node.callback)(
+                        node.call(
                             &mut cx,
                             &match input.state {
                                 ElementState::Pressed => Interaction::KeyDown {
@@ -238,7 +291,7 @@ pub fn run(
                 ..
             } => {
                 if let Some(node) = &focused_node {
-                    (*node.callback)(&mut cx, &Interaction::ReceiveCharacter { character });
+                    node.call(&mut cx, &Interaction::ReceiveCharacter { character });
                 }
             }
             Event::WindowEvent {
@@ -248,13 +301,16 @@ pub fn run(
                 modifiers = mods;
             }
             Event::MainEventsCleared => {
-                if (std::time::Instant::now() - last_redraw).as_millis() > 8 {
-                    // limit framerate to 120 fps
+                if redraw_pending && Instant::now() >= last_redraw + FRAME_INTERVAL {
                     winit_window.request_redraw();
                 }
             }
             Event::RedrawRequested(_window_id) => {
-                last_redraw = std::time::Instant::now();
+                last_redraw = Instant::now();
+                redraw_pending = false;
+                cx.begin_frame(size);
+                let mut rendered = Ok(());
+                let mut resolve_error = None;
                 renderer
                     .draw(&window, |canvas, _coordinate_system_helper| {
                         let w = f(&WindowInfo { size }, &mut cx, &mut resources);
@@ -264,9 +320,8 @@ pub fn run(
                             w.body,
                             std::mem::replace(&mut curr_node, ResolvedNode::Null),
                             &Rect::new(Point2::new(0., 0.), size),
-                            &mut hovered_node,
-                            &mut pressed_node,
-                            &mut focused_node,
+                            &mut [&mut hovered_node, &mut pressed_node, &mut focused_node],
+                            &mut resolve_error,
                         );
 
                         canvas.clear(skulpin::skia_safe::Color::from_argb(
@@ -276,21 +331,57 @@ pub fn run(
                             (w.background.blue * 255.) as _,
                         ));
 
-                        curr_node.perform_layout();
+                        curr_node.perform_layout(BoxConstraints::loose(size));
+                        // layouts kept while off-screen have scrolled into view, so build them properly in the next frame
+                        if curr_node.reveals_stale(&Rect::new(Point2::new(0., 0.), size)) {
+                            cx.request_redraw();
+                        }
+                        curr_node.invoke_captures(&mut cx);
+                        curr_node.advance_transitions(&mut cx);
 
-                        render_tree(
+                        rendered = render_tree(
                             &mut cx,
+                            &mut resources,
                             canvas,
                             &curr_node,
                             &Rect::new(Point2::new(0., 0.), size),
-                        )
-                        .unwrap();
+                        );
                     })
                     .expect("failed to render using vulkan");
+
+                if let Err(err) = rendered {
+                    error = Some(Error::from(err));
+                    *control_flow = ControlFlow::Exit;
+                } else if let Some(err) = resolve_error {
+                    error = Some(Error::from(err));
+                    *control_flow = ControlFlow::Exit;
+                }
+            }
+            Event::RedrawEventsCleared => {
+                if cx.take_redraw_request() {
+                    redraw_pending = true;
+                }
+
+                // idle callbacks wait until there is neither input nor a frame left to handle
+                if !redraw_pending {
+                    cx.invoke_on_idle();
+                    redraw_pending = cx.take_redraw_request();
+                }
+
+                if *control_flow != ControlFlow::Exit {
+                    *control_flow = if redraw_pending {
+                        // wake up once the next frame is due
+                        ControlFlow::WaitUntil(last_redraw + FRAME_INTERVAL)
+                    } else {
+                        ControlFlow::Wait
+                    };
+                }
             }
             _ => {}
         }
     });
+
+    error.map_or(Ok(()), Err)
 }
 
 fn compare_interact(a: &Option<InteractNode>, b: &Option<InteractNode>) -> bool {
@@ -303,7 +394,7 @@ fn compare_interact(a: &Option<InteractNode>, b: &Option<InteractNode>) -> bool
 
 fn try_callback(node: &Option<InteractNode>, cx: &mut cx::Cx, event: &Interaction) {
     if let Some(node) = node {
-        (*node.callback)(cx, event);
+        node.call(cx, event);
     }
 }
 
@@ -313,42 +404,90 @@ fn try_set_callback(node: &mut Option<InteractNode>, cb: &Rc<dyn Fn(&mut cx::Cx,
     }
 }
 
-fn node_at_point_tree<'a>(
+fn interact_node(node: &ResolvedNode, transform: Transform2) -> Option<InteractNode> {
+    if let ResolvedNode::Interact { callback, id, .. } = node {
+        Some(InteractNode {
+            callback: Rc::clone(callback),
+            id: *id,
+            transform,
+        })
+    } else {
+        None
+    }
+}
+
+/// Collects the `Interact` nodes under `point` within the top-most layer that has any, from outermost to innermost.
+///
+/// Each node is paired with the transform from window space to the space of that node.
+fn node_at_point<'a>(
     point: Point2,
     node: &'a ResolvedNode,
-    mut last_interact: Option<&'a ResolvedNode>,
-) -> Option<&'a ResolvedNode> {
-    if node.rect().contains(point) {
-        if node.is_interact() {
-            last_interact = Some(node);
-        }
-
-        for child in node.children() {
-            if let node @ Some(_) = node_at_point_tree(point, child, last_interact) {
-                return node;
+    path: &mut Vec<(&'a ResolvedNode, Transform2)>,
+) {
+    for (layer, transform) in node.layers().into_iter().rev() {
+        if let Some(inverse) = transform.inverse() {
+            if node_at_point_tree(inverse.transform_point(point), layer, inverse, path) {
+                return;
             }
         }
+    }
+}
+
+/// Collects the `Interact` nodes under `point`, from outermost to innermost.
+///
+/// `point` is in the space of `node`'s parent, and `transform` maps to it from window space.
+/// Children are visited in reverse paint order, so that whatever is drawn on top receives input first.
+/// Returns `true` if any were found within `node`.
+fn node_at_point_tree<'a>(
+    point: Point2,
+    node: &'a ResolvedNode,
+    transform: Transform2,
+    path: &mut Vec<(&'a ResolvedNode, Transform2)>,
+) -> bool {
+    // a transform node covers its rect in its own transformed space
+    let (point, transform) = match node.local_transform().map(|local| local.inverse()) {
+        Some(Some(inverse)) => (inverse.transform_point(point), transform.then(&inverse)),
+        Some(None) => return false,
+        None => (point, transform),
+    };
+
+    if !node.hit_test(point) {
+        return false;
+    }
+
+    let len = path.len();
+
+    if node.is_interact() {
+        path.push((node, transform));
+    }
 
-        if node.is_interact() {
-            Some(node)
-        } else {
-            last_interact
+    for child in node.paint_children().into_iter().rev() {
+        if node_at_point_tree(point, child, transform, path) {
+            return true;
         }
-    } else {
-        last_interact
     }
+
+    if node.passes_through(point) {
+        path.truncate(len);
+        return false;
+    }
+
+    path.len() > len
 }
 
+/// Resolves `new`, reusing what it can of `old`.
+///
+/// `interacting` holds the hovered, pressed and focused nodes, which take on the callbacks of their new counterparts.
+/// Nodes which fail to resolve are left out, with the first failure stored in `error`.
 fn diff_resolve(
     resources: &mut Resources,
     new: Node,
     old: ResolvedNode,
     cull: &crate::Rect,
-    hovered: &mut Option<InteractNode>,
-    pressed: &mut Option<InteractNode>,
-    focused: &mut Option<InteractNode>,
+    interacting: &mut [&mut Option<InteractNode>; 3],
+    error: &mut Option<crate::Error>,
 ) -> ResolvedNode {
-    match (new, old) {
+    let resolved = match (new, old) {
         (Node::Null, ResolvedNode::Null) => Ok(Some(ResolvedNode::Null)),
         (
             Node::Interact {
@@ -359,38 +498,19 @@ fn diff_resolve(
             },
             ResolvedNode::Interact { child, .. },
         ) => {
-            if compare_interact(
-                hovered,
-                &Some(InteractNode {
-                    callback: callback.clone(),
-                    id,
-                }),
-            ) {
-                try_set_callback(hovered, &callback);
-            }
-
-            if compare_interact(
-                pressed,
-                &Some(InteractNode {
-                    callback: callback.clone(),
-                    id,
-                }),
-            ) {
-                try_set_callback(pressed, &callback);
-            }
-
-            if compare_interact(
-                focused,
-                &Some(InteractNode {
-                    callback: callback.clone(),
-                    id,
-                }),
-            ) {
-                try_set_callback(focused, &callback);
+            for node in interacting.iter_mut() {
+                if matches!(node, Some(node) if node.id == id) {
+                    try_set_callback(node, &callback);
+                }
             }
 
             let child = Box::new(diff_resolve(
-                resources, *new_child, *child, cull, hovered, pressed, focused,
+                resources,
+                *new_child,
+                *child,
+                cull,
+                interacting,
+                error,
             ));
             Ok(Some(ResolvedNode::Interact {
                 rect: crate::Rect::new(Default::default(), child.size()),
@@ -400,6 +520,191 @@ fn diff_resolve(
                 passthrough,
             }))
         }
+        (
+            Node::Capture {
+                child: new_child,
+                callback,
+            },
+            ResolvedNode::Capture { child, .. },
+        ) => {
+            let child = Box::new(diff_resolve(
+                resources,
+                *new_child,
+                *child,
+                cull,
+                interacting,
+                error,
+            ));
+            Ok(Some(ResolvedNode::Capture {
+                rect: crate::Rect::new(Default::default(), child.size()),
+                child,
+                callback,
+            }))
+        }
+        (
+            Node::Keyed {
+                key: new_key,
+                child: new_child,
+            },
+            ResolvedNode::Keyed { key, child, .. },
+        ) if new_key == key => {
+            let child = Box::new(diff_resolve(
+                resources,
+                *new_child,
+                *child,
+                cull,
+                interacting,
+                error,
+            ));
+            Ok(Some(ResolvedNode::Keyed {
+                rect: crate::Rect::new(Default::default(), child.size()),
+                child,
+                key,
+            }))
+        }
+        (
+            Node::Layer {
+                z_order,
+                child: new_child,
+            },
+            ResolvedNode::Layer { child, .. },
+        ) => {
+            let child = Box::new(diff_resolve(
+                resources,
+                *new_child,
+                *child,
+                cull,
+                interacting,
+                error,
+            ));
+            Ok(Some(ResolvedNode::Layer {
+                rect: crate::Rect::new(Default::default(), child.size()),
+                child,
+                z_order,
+            }))
+        }
+        (
+            Node::Clip {
+                corner_radius,
+                child: new_child,
+            },
+            ResolvedNode::Clip { child, .. },
+        ) => {
+            let child = Box::new(diff_resolve(
+                resources,
+                *new_child,
+                *child,
+                cull,
+                interacting,
+                error,
+            ));
+            Ok(Some(ResolvedNode::Clip {
+                rect: crate::Rect::new(Default::default(), child.size()),
+                child,
+                corner_radii: corner_radius,
+            }))
+        }
+        (
+            Node::Transform {
+                transform,
+                origin,
+                child: new_child,
+            },
+            ResolvedNode::Transform { child, rect, .. },
+        ) => {
+            // the children are culled in the transformed space; the new transform is applied about the previous rect, as the new one is only known after layout
+            let cull = crate::node::transform_about(&transform, origin, &rect)
+                .inverse()
+                .map(|inverse| inverse.outer_transformed_rect(cull))
+                .unwrap_or(*cull);
+
+            let child = Box::new(diff_resolve(
+                resources,
+                *new_child,
+                *child,
+                &cull,
+                interacting,
+                error,
+            ));
+            Ok(Some(ResolvedNode::Transform {
+                rect: crate::Rect::new(Default::default(), child.size()),
+                child,
+                transform,
+                origin,
+            }))
+        }
+        (
+            Node::Effect {
+                effect,
+                child: new_child,
+            },
+            ResolvedNode::Effect { child, .. },
+        ) => {
+            let child = Box::new(diff_resolve(
+                resources,
+                *new_child,
+                *child,
+                cull,
+                interacting,
+                error,
+            ));
+            Ok(Some(ResolvedNode::Effect {
+                rect: crate::Rect::new(Default::default(), child.size()),
+                child,
+                effect,
+            }))
+        }
+        (
+            Node::Path { path, fill, stroke },
+            ResolvedNode::Path {
+                path: old_path,
+                sk_path,
+                outline,
+                stroke: old_stroke,
+                ..
+            },
+        ) if path == old_path
+            && stroke.as_ref().map(Stroke::geometry)
+                == old_stroke.as_ref().map(Stroke::geometry) =>
+        {
+            // layout may have stretched the old rect, so go back to the natural size (see `resolve_path`)
+            let bounds = crate::node::path_bounds(&sk_path, outline.as_ref());
+            Ok(Some(ResolvedNode::Path {
+                rect: crate::Rect::new(
+                    Default::default(),
+                    Size2::new(bounds.width(), bounds.height()),
+                ),
+                path,
+                sk_path,
+                outline,
+                fill,
+                stroke,
+            }))
+        }
+        (
+            Node::Image {
+                image,
+                size,
+                fit,
+                align,
+                quality,
+            },
+            ResolvedNode::Image {
+                image: old_image,
+                sk_image,
+                ..
+            },
+        ) if Rc::ptr_eq(&image, &old_image) => Ok(Some(ResolvedNode::Image {
+            rect: crate::Rect::new(
+                Default::default(),
+                size.unwrap_or_else(|| Size2::new(image.width() as _, image.height() as _)),
+            ),
+            image,
+            sk_image,
+            fit,
+            align,
+            quality,
+        })),
         (
             Node::Text {
                 text: new_text,
@@ -419,8 +724,22 @@ fn diff_resolve(
             },
         ) => {
             let new_size = new_size.unwrap_or(resources.fallback_text_size);
-
-            if new_text != text || new_font != font || (new_size - size).abs() > std::f32::EPSILON {
+            // fonts added since may change which fonts the text is shaped with, in which case the shaped text is no longer cached
+            let fonts_changed = !resources
+                .primary_font(&new_font)
+                .map_or(false, |primary| Rc::ptr_eq(primary, &font_data))
+                || (blob.is_some()
+                    && !resources.shaper_cache.contains_key(&(
+                        new_text.clone(),
+                        new_font.clone(),
+                        OrderedFloat(new_size),
+                    )));
+
+            if new_text != text
+                || new_font != font
+                || (new_size - size).abs() > std::f32::EPSILON
+                || fonts_changed
+            {
                 Node::Text {
                     text: new_text,
                     font: new_font,
@@ -441,53 +760,225 @@ fn diff_resolve(
                 }))
             }
         }
+        (
+            Node::Paragraph {
+                text: new_text,
+                font: new_font,
+                size: new_size,
+                fill: new_fill,
+                style: new_style,
+            },
+            ResolvedNode::Paragraph {
+                text,
+                font,
+                size,
+                fill,
+                style,
+                paragraph,
+                rect,
+            },
+        ) => {
+            let new_size = new_size.unwrap_or(resources.fallback_text_size);
+            let new_fill = new_fill.unwrap_or_else(|| resources.fallback_text_fill.clone());
+
+            if new_text != text
+                || new_font != font
+                || (new_size - size).abs() > f32::EPSILON
+                || new_fill != fill
+                || new_style != style
+            {
+                Node::Paragraph {
+                    text: new_text,
+                    font: new_font,
+                    size: Some(new_size),
+                    fill: Some(new_fill),
+                    style: new_style,
+                }
+                .resolve(resources)
+            } else {
+                Ok(Some(ResolvedNode::Paragraph {
+                    text,
+                    font,
+                    size,
+                    fill,
+                    style,
+                    paragraph,
+                    rect,
+                }))
+            }
+        }
+        (
+            Node::RichText {
+                spans: new_spans,
+                style: new_style,
+            },
+            ResolvedNode::RichText {
+                spans,
+                style,
+                paragraph,
+                rect,
+            },
+        ) => {
+            let unchanged = new_style == style
+                && new_spans.len() == spans.len()
+                && new_spans
+                    .iter()
+                    .zip(&spans)
+                    .all(|(new_span, span)| new_span.looks_like(span));
+
+            if unchanged {
+                // click handlers are always taken from the new spans
+                Ok(Some(ResolvedNode::RichText {
+                    spans: new_spans,
+                    style,
+                    paragraph,
+                    rect,
+                }))
+            } else {
+                Node::RichText {
+                    spans: new_spans,
+                    style: new_style,
+                }
+                .resolve(resources)
+            }
+        }
+        (
+            Node::Transition {
+                spec,
+                child: new_child,
+            },
+            ResolvedNode::Transition { child, state, .. },
+        ) => {
+            let child = Box::new(diff_resolve(
+                resources,
+                *new_child,
+                *child,
+                cull,
+                interacting,
+                error,
+            ));
+            Ok(Some(ResolvedNode::Transition {
+                rect: crate::Rect::new(Default::default(), child.size()),
+                child,
+                spec,
+                state,
+            }))
+        }
         (
             Node::Layout {
                 layout,
                 children: new_children,
             },
             ResolvedNode::Layout {
-                mut children, rect, ..
+                layout: old_layout,
+                children,
+                exiting,
+                baseline,
+                rect,
+                ..
             },
         ) => {
-            if !cull.intersects(&rect) {
-                return ResolvedNode::Null;
+            // layouts which were off-screen in the previous frame keep their previous children (and their state) rather than being rebuilt;
+            // the rect of a layout which was never laid out says nothing about where it will be
+            if !rect.is_empty() && !cull.intersects(&rect) {
+                return ResolvedNode::Layout {
+                    layout: old_layout,
+                    children,
+                    exiting: exiting
+                        .into_iter()
+                        .map(|mut child| {
+                            child.translate(-rect.origin);
+                            child
+                        })
+                        .collect(),
+                    baseline,
+                    rect,
+                    cache: Default::default(),
+                    stale: true,
+                };
             }
 
-            match children.len().cmp(&new_children.len()) {
-                Ordering::Less => children.append(&mut vec![
-                    ResolvedNode::Null;
-                    new_children.len() - children.len()
-                ]),
-                Ordering::Greater => children.truncate(new_children.len()),
-                _ => {}
+            // siblings sharing a key can't be told apart, so they are matched in order along with the unkeyed children
+            let mut key_counts = FxHashMap::<Key, usize>::default();
+            for new_child in &new_children {
+                if let Node::Keyed { key, .. } = new_child {
+                    *key_counts.entry(*key).or_default() += 1;
+                }
+            }
+            let is_unique = |key: &Key| key_counts.get(key).map_or(true, |&count| count == 1);
+
+            // keyed children are matched by key (wherever they moved to), the rest are matched in order
+            let mut keyed_children = FxHashMap::<Key, ResolvedNode>::default();
+            let mut unkeyed_children = Vec::with_capacity(children.len());
+            for child in children {
+                match child
+                    .key()
+                    .filter(|key| is_unique(key) && !keyed_children.contains_key(key))
+                {
+                    Some(key) => {
+                        keyed_children.insert(key, child);
+                    }
+                    None => unkeyed_children.push(child),
+                }
             }
+            let mut unkeyed_children = unkeyed_children.into_iter();
 
+            let mut exiting = exiting;
             let children = new_children
                 .into_iter()
-                .zip(children.into_iter())
-                .map(|(new_child, old_child)| {
-                    diff_resolve(
-                        resources, new_child, old_child, cull, hovered, pressed, focused,
-                    )
+                .map(|new_child| {
+                    let old_child = match &new_child {
+                        Node::Keyed { key, .. } if is_unique(key) => {
+                            keyed_children.remove(key).or_else(|| {
+                                // a key returning while it fades out picks up where it is, rather than appearing twice
+                                let index =
+                                    exiting.iter().position(|child| child.key() == Some(*key))?;
+                                let mut child = exiting.remove(index);
+                                child.enter();
+                                Some(child)
+                            })
+                        }
+                        _ => unkeyed_children.next(),
+                    }
+                    .unwrap_or(ResolvedNode::Null);
+
+                    diff_resolve(resources, new_child, old_child, cull, interacting, error)
                 })
                 .collect::<Vec<_>>();
 
-            let size = layout.size(
-                &children
-                    .iter()
-                    .map(|child| child.size())
-                    .collect::<Vec<_>>(),
-            );
+            // removed keyed transitions linger until they have faded out, positioned relative to this layout so that they move along with it
+            let exiting = exiting
+                .into_iter()
+                .chain(keyed_children.into_values().filter_map(|mut child| {
+                    if child.exit() {
+                        Some(child)
+                    } else {
+                        None
+                    }
+                }))
+                .map(|mut child| {
+                    child.translate(-rect.origin);
+                    child
+                })
+                .collect();
 
             Ok(Some(ResolvedNode::Layout {
                 layout,
                 children,
-                rect: crate::Rect::new(Default::default(), size),
+                exiting,
+                baseline,
+                rect,
+                cache: Default::default(),
+                stale: false,
             }))
         }
         (new, _) => new.resolve(resources),
-    }
-    .unwrap()
-    .unwrap()
+    };
+
+    resolved
+        .unwrap_or_else(|err| {
+            error.get_or_insert(err);
+            None
+        })
+        .unwrap_or(ResolvedNode::Null)
 }
@@ -1,11 +1,20 @@
-use crate::{call, id::Id, node::Resources};
+use crate::{
+    anim::{Animatable, Animation, AnimationSpec},
+    call,
+    id::Id,
+    node::{capture, BoxConstraints, BoxLayout, IntoNode, Node, ResolvedNode, Resources},
+    Rect, Size2,
+};
 use futures::FutureExt;
 use fxhash::FxHashMap;
 use std::{
     any::{Any, TypeId},
+    cell::Cell,
     collections::hash_map::Entry,
     future::Future,
     marker::PhantomData,
+    rc::Rc,
+    time::{Duration, Instant},
 };
 use winit::event_loop::EventLoopProxy;
 
@@ -38,6 +47,35 @@ pub struct StaticState;
 
 pub type ExecHandle<T> = Option<tokio::task::JoinHandle<T>>;
 
+/// Timing of the frame currently being built, see `Cx::frame_time`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FrameTime {
+    /// Monotonic time since the event loop started.
+    pub elapsed: Duration,
+    /// Time since the previous frame (or since the event loop started, for the first frame).
+    pub delta: Duration,
+}
+
+type FrameCallback = Box<dyn FnOnce(&mut Cx, FrameTime)>;
+type IdleCallback = Box<dyn FnOnce(&mut Cx)>;
+
+/// Lays out its only child with its own constraints, remembering them for `Cx::layout_builder`.
+struct ConstraintsProbe(Rc<Cell<Option<BoxConstraints>>>);
+
+impl BoxLayout for ConstraintsProbe {
+    fn layout(&self, constraints: BoxConstraints, children: &mut [ResolvedNode]) -> Size2 {
+        self.0.set(Some(constraints));
+        children
+            .iter_mut()
+            .map(|child| {
+                let size = child.layout(constraints);
+                child.set_position(Default::default());
+                size
+            })
+            .fold(constraints.constrain(Size2::zero()), Size2::max)
+    }
+}
+
 pub struct Cx {
     state: FxHashMap<(TypeId, Id), Box<dyn Any>>,
     cache: FxHashMap<(TypeId, Id), Cached>,
@@ -46,6 +84,14 @@ pub struct Cx {
     on_lifecycle: FxHashMap<Id, (bool, bool, Box<dyn FnMut(&mut Cx, Lifecycle, &Resources)>)>,
     events: FxHashMap<TypeId, Box<dyn Any>>,
     proxy: EventLoopProxy<()>,
+    redraw: bool,
+    stop_propagation: bool,
+    start: Instant,
+    frame: Instant,
+    delta: Duration,
+    viewport: Size2,
+    on_frame: Vec<FrameCallback>,
+    on_idle: Vec<(Id, IdleCallback)>,
 }
 
 impl Cx {
@@ -58,6 +104,14 @@ impl Cx {
             on_lifecycle: Default::default(),
             events: Default::default(),
             proxy,
+            redraw: false,
+            stop_propagation: false,
+            start: Instant::now(),
+            frame: Instant::now(),
+            delta: Duration::from_secs(0),
+            viewport: Size2::zero(),
+            on_frame: Vec::new(),
+            on_idle: Vec::new(),
         }
     }
 
@@ -197,6 +251,147 @@ impl Cx {
         }
     }
 
+    /// Measures the laid-out rectangle of the node returned by `f`.
+    ///
+    /// The rectangle is stored once layout has been performed (hence it is `None` on the first frame), and a redraw is requested whenever it changes.
+    #[track_caller]
+    pub fn measure<N: IntoNode>(
+        &mut self,
+        f: impl FnOnce() -> N,
+    ) -> (Node, Handle<Option<Rect>, State>) {
+        let measured = self.state(|| None);
+        let node = capture(f(), move |cx, node| {
+            let rect = Some(node.rect());
+            if *cx.at(measured) != rect {
+                *cx.at(measured) = rect;
+                cx.request_redraw();
+            }
+        });
+        (node, measured)
+    }
+
+    /// Builds a node from the constraints it is laid out within, e.g. to switch to a single column when narrow.
+    ///
+    /// Constraints are only known once layout has been performed, so `f` receives the constraints of the previous frame (unbounded on the first frame), and a redraw is requested whenever they change.
+    #[track_caller]
+    pub fn layout_builder<N: IntoNode>(
+        &mut self,
+        f: impl FnOnce(&mut Cx, BoxConstraints) -> N,
+    ) -> Node {
+        let constraints = self.state(|| None);
+        let current = self.at(constraints).unwrap_or_default();
+        let child = f(self, current).into_node();
+
+        let probe = Rc::new(Cell::new(None));
+        let node = Node::Layout {
+            layout: Rc::new(ConstraintsProbe(Rc::clone(&probe))),
+            children: vec![child],
+        };
+
+        capture(node, move |cx, _| {
+            let laid_out = probe.get();
+            if laid_out.is_some() && *cx.at(constraints) != laid_out {
+                *cx.at(constraints) = laid_out;
+                cx.request_redraw();
+            }
+        })
+    }
+
+    /// Schedules another frame, e.g. after changing state outside of an interaction.
+    pub fn request_redraw(&mut self) {
+        self.redraw = true;
+    }
+
+    pub(crate) fn take_redraw_request(&mut self) -> bool {
+        std::mem::replace(&mut self.redraw, false) || !self.on_frame.is_empty()
+    }
+
+    /// Stops the interaction currently being handled from bubbling up to outer interact nodes, e.g. once a scroll has been consumed.
+    pub fn stop_propagation(&mut self) {
+        self.stop_propagation = true;
+    }
+
+    pub(crate) fn take_stop_propagation(&mut self) -> bool {
+        std::mem::replace(&mut self.stop_propagation, false)
+    }
+
+    /// Advances the frame clock, then invokes the `on_frame` callbacks registered while building the previous frame.
+    ///
+    /// Every animation within a frame is evaluated at the same instant, and every component sees the same `viewport`.
+    pub(crate) fn begin_frame(&mut self, viewport: Size2) {
+        self.viewport = viewport;
+
+        let now = Instant::now();
+        self.delta = now - self.frame;
+        self.frame = now;
+
+        let time = self.frame_time();
+        for on_frame in std::mem::take(&mut self.on_frame) {
+            on_frame(self, time);
+        }
+    }
+
+    /// Returns the size of the window the UI is being built for.
+    pub fn viewport(&self) -> Size2 {
+        self.viewport
+    }
+
+    pub(crate) fn frame(&self) -> Instant {
+        self.frame
+    }
+
+    /// Returns the timing of the frame currently being built.
+    ///
+    /// This is fixed for the duration of a frame, so everything built within it agrees on the time.
+    pub fn frame_time(&self) -> FrameTime {
+        FrameTime {
+            elapsed: self.frame - self.start,
+            delta: self.delta,
+        }
+    }
+
+    /// Invokes `f` at the start of the next frame, before the UI is built.
+    ///
+    /// Redraws are requested for as long as `on_frame` is called while building each frame, so a component stops receiving frames as soon as it stops calling `on_frame`.
+    pub fn on_frame(&mut self, f: impl FnOnce(&mut Cx, FrameTime) + 'static) {
+        self.on_frame.push(Box::new(f));
+    }
+
+    /// Invokes `f` once the event loop has no input left to handle and no frame left to draw, including frames requested by animations.
+    ///
+    /// Idle callbacks run once. Registering again from the same call site before then replaces the pending callback, so registering while building every frame doesn't pile them up.
+    /// No frame is drawn afterwards unless `f` calls `request_redraw`; otherwise registering while building would redraw forever.
+    #[track_caller]
+    pub fn on_idle(&mut self, f: impl FnOnce(&mut Cx) + 'static) {
+        let id = call(Id::current);
+        match self.on_idle.iter_mut().find(|(other, _)| *other == id) {
+            Some((_, callback)) => *callback = Box::new(f),
+            None => self.on_idle.push((id, Box::new(f))),
+        }
+    }
+
+    pub(crate) fn invoke_on_idle(&mut self) {
+        for (_, on_idle) in std::mem::take(&mut self.on_idle) {
+            on_idle(self);
+        }
+    }
+
+    /// Returns the current value of an animation towards `target`.
+    ///
+    /// The value starts out at `target`. Whenever `target` changes (including mid-flight), the animation restarts from the current value as described by `spec`.
+    /// Redraws are requested for as long as the animation is in flight.
+    #[track_caller]
+    pub fn animate<T: Animatable>(&mut self, target: T, spec: AnimationSpec) -> T {
+        let now = self.frame;
+        let animation = self.state(|| Animation::new(target.to_components(), now));
+        let animation = self.at(animation);
+        let value = animation.update(target.to_components(), &spec, now);
+        if animation.is_running() {
+            self.request_redraw();
+        }
+        T::from_components(value)
+    }
+
     #[cfg(feature = "tokio")]
     pub fn exec<Fu>(&mut self, f: Fu) -> tokio::task::JoinHandle<Fu::Output>
     where
@@ -1,5 +1,6 @@
 use thiserror::Error;
 
+pub mod anim;
 pub mod backend;
 pub mod cx;
 pub mod id;
@@ -21,6 +22,7 @@ pub type Point3 = euclid::Point3D<f32, euclid::UnknownUnit>;
 pub type Size2 = euclid::Size2D<f32, euclid::UnknownUnit>;
 pub type Sides2 = euclid::SideOffsets2D<f32, euclid::UnknownUnit>;
 pub type Rect = euclid::Rect<f32, euclid::UnknownUnit>;
+pub type Transform2 = euclid::Transform2D<f32, euclid::UnknownUnit, euclid::UnknownUnit>;
 
 pub fn rgba(r: u8, g: u8, b: u8, a: u8) -> Color {
     Color::new(
@@ -85,6 +87,10 @@ pub enum Error {
     SkiaFont,
     #[error("invalid child placed in an interact/capture node")]
     EmptyNode,
+    #[error("error creating skia image")]
+    ImageCreation,
+    #[error("error compiling shader: {0}")]
+    ShaderCompilation(String),
 }
 
 pub trait ToSkia<T> {
@@ -102,6 +108,14 @@ impl ToSkia<skia::Rect> for Rect {
     }
 }
 
+impl ToSkia<skia::Matrix> for Transform2 {
+    fn to_skia(&self) -> skia::Matrix {
+        skia::Matrix::new_all(
+            self.m11, self.m21, self.m31, self.m12, self.m22, self.m32, 0., 0., 1.,
+        )
+    }
+}
+
 impl ToSkia<skia::Point> for Point2 {
     fn to_skia(&self) -> skia::Point {
         skia::Point::new(self.x, self.y)
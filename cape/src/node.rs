@@ -1,9 +1,25 @@
-use crate::{call, cx::Cx, id::Id, size2, Color, Error, Image, Point2, Rect, Size2};
+use crate::{
+    anim::{Animatable, Animation, AnimationSpec},
+    call,
+    cx::Cx,
+    id::Id,
+    point2, size2, Color, Error, Image, Point2, Rect, Sides2, Size2, ToSkia, Transform2,
+};
 use fxhash::FxHashMap;
 use ordered_float::OrderedFloat;
 use skulpin::skia_safe as sk;
-use std::{rc::Rc, sync::Arc};
+use std::{
+    cell::RefCell,
+    ops::Range,
+    rc::{Rc, Weak},
+    sync::Arc,
+    time::Instant,
+};
 
+/// Describes how a shape is filled.
+///
+/// Points (e.g. `begin`, `center`) are relative to the size of the shape, whereas radii are absolute.
+/// Gradient `transform`s are applied about the origin of the shape.
 #[derive(Debug, Clone, PartialEq)]
 pub enum Paint {
     Solid(Color),
@@ -11,24 +27,680 @@ pub enum Paint {
         stops: Vec<(f32, Color)>,
         begin: Point2,
         end: Point2,
+        tile_mode: sk::TileMode,
+        transform: Option<Transform2>,
     },
     RadialGradient {
         stops: Vec<(f32, Color)>,
         center: Point2,
         radius: f32,
+        tile_mode: sk::TileMode,
+        transform: Option<Transform2>,
+    },
+    /// Conic gradient sweeping clockwise around `center`, from `start_angle` to `end_angle` (in degrees, where 0 is along the positive X axis).
+    SweepGradient {
+        stops: Vec<(f32, Color)>,
+        center: Point2,
+        start_angle: f32,
+        end_angle: f32,
+        tile_mode: sk::TileMode,
+        transform: Option<Transform2>,
+    },
+    /// Gradient between two circles.
+    TwoPointConicalGradient {
+        stops: Vec<(f32, Color)>,
+        start: Point2,
+        start_radius: f32,
+        end: Point2,
+        end_radius: f32,
+        tile_mode: sk::TileMode,
+        transform: Option<Transform2>,
     },
     Image(Rc<Image>),
+    /// Blurs whatever is behind the shape, then tints it. Only supported as the background of rectangles, where it may be wrapped in a `Composite`.
     Blur {
         radius: f32,
         tint: Color,
     },
+    /// SkSL shader, e.g. `uniform float4 rect; void main(float2 p, inout half4 color) { ... }`.
+    ///
+    /// Shaders are compiled once per source (see `Resources::shader`), and are supported by both the GPU and CPU raster backends.
+    Shader {
+        source: String,
+        uniforms: Vec<(String, ShaderUniform)>,
+    },
+    /// Another paint with a blend mode and/or opacity applied.
+    Composite {
+        paint: Box<Paint>,
+        blend_mode: Option<sk::BlendMode>,
+        opacity: f32,
+    },
+}
+
+impl Paint {
+    pub fn linear_gradient(stops: Vec<(f32, Color)>, begin: Point2, end: Point2) -> Self {
+        Paint::LinearGradient {
+            stops,
+            begin,
+            end,
+            tile_mode: sk::TileMode::Clamp,
+            transform: None,
+        }
+    }
+
+    pub fn radial_gradient(stops: Vec<(f32, Color)>, center: Point2, radius: f32) -> Self {
+        Paint::RadialGradient {
+            stops,
+            center,
+            radius,
+            tile_mode: sk::TileMode::Clamp,
+            transform: None,
+        }
+    }
+
+    /// Full turn sweep gradient around `center`.
+    pub fn sweep_gradient(stops: Vec<(f32, Color)>, center: Point2) -> Self {
+        Paint::SweepGradient {
+            stops,
+            center,
+            start_angle: 0.,
+            end_angle: 360.,
+            tile_mode: sk::TileMode::Clamp,
+            transform: None,
+        }
+    }
+
+    /// Returns this paint drawn with `opacity`, on top of any existing opacity.
+    pub fn with_opacity(self, opacity: f32) -> Self {
+        match self {
+            Paint::Composite {
+                paint,
+                blend_mode,
+                opacity: existing,
+            } => Paint::Composite {
+                paint,
+                blend_mode,
+                opacity: existing * opacity,
+            },
+            paint => Paint::Composite {
+                paint: Box::new(paint),
+                blend_mode: None,
+                opacity,
+            },
+        }
+    }
+
+    /// Returns this paint drawn with `blend_mode`.
+    pub fn with_blend_mode(self, blend_mode: sk::BlendMode) -> Self {
+        match self {
+            Paint::Composite { paint, opacity, .. } => Paint::Composite {
+                paint,
+                blend_mode: Some(blend_mode),
+                opacity,
+            },
+            paint => Paint::Composite {
+                paint: Box::new(paint),
+                blend_mode: Some(blend_mode),
+                opacity: 1.,
+            },
+        }
+    }
+}
+
+/// Image filter applied to a rendered subtree, see `Effect`.
+///
+/// Blur radii follow CSS, as with `Shadow::blur`: the blur fades out over roughly the radius.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    Blur {
+        radius: f32,
+    },
+    DropShadow {
+        offset: Point2,
+        radius: f32,
+        color: Color,
+    },
+    /// Row-major 4x5 matrix applied to each (unpremultiplied) RGBA color.
+    ColorMatrix([f32; 20]),
+}
+
+impl Filter {
+    /// Color matrix which desaturates to grayscale, e.g. for disabled widgets.
+    pub fn grayscale() -> Self {
+        const R: f32 = 0.2126;
+        const G: f32 = 0.7152;
+        const B: f32 = 0.0722;
+
+        #[rustfmt::skip]
+        let matrix = [
+            R, G, B, 0., 0.,
+            R, G, B, 0., 0.,
+            R, G, B, 0., 0.,
+            0., 0., 0., 1., 0.,
+        ];
+
+        Filter::ColorMatrix(matrix)
+    }
+}
+
+/// Group effect which renders a subtree into a layer of its own before compositing it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Effect {
+    pub opacity: f32,
+    pub blend_mode: Option<sk::BlendMode>,
+    /// Applied in order, each taking the output of the previous one.
+    pub filters: Vec<Filter>,
+}
+
+impl Default for Effect {
+    fn default() -> Self {
+        Effect {
+            opacity: 1.,
+            blend_mode: None,
+            filters: Vec::new(),
+        }
+    }
+}
+
+impl Effect {
+    /// Returns `true` if this effect does nothing, in which case no layer is needed.
+    pub fn is_noop(&self) -> bool {
+        self.opacity >= 1. && self.blend_mode.is_none() && self.filters.is_empty()
+    }
+}
+
+/// How an image is sized to fit the rectangle of its node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageFit {
+    /// Stretched to fill the rectangle, ignoring the aspect ratio.
+    Fill,
+    /// Scaled to fit entirely within the rectangle, keeping the aspect ratio.
+    Contain,
+    /// Scaled to cover the entire rectangle, keeping the aspect ratio and clipping the overflow.
+    Cover,
+    /// Drawn at its natural size, clipping the overflow.
+    None,
+    /// The smaller of `None` and `Contain`.
+    ScaleDown,
+}
+
+impl Default for ImageFit {
+    fn default() -> Self {
+        ImageFit::Fill
+    }
+}
+
+impl ImageFit {
+    /// Returns the rectangle to draw an image of `size` into, within `bounds`.
+    ///
+    /// `align` is relative to the size of `bounds`, e.g. `(0.5, 0.5)` centers the image.
+    pub fn apply(&self, size: Size2, bounds: Rect, align: Point2) -> Rect {
+        let sx = bounds.size.width / size.width.max(1.);
+        let sy = bounds.size.height / size.height.max(1.);
+        let fitted = match self {
+            ImageFit::Fill => return bounds,
+            ImageFit::Contain => size * sx.min(sy),
+            ImageFit::Cover => size * sx.max(sy),
+            ImageFit::None => size,
+            ImageFit::ScaleDown => size * sx.min(sy).min(1.),
+        };
+        let origin = bounds.origin
+            + point2(
+                (bounds.size.width - fitted.width) * align.x,
+                (bounds.size.height - fitted.height) * align.y,
+            )
+            .to_vector();
+        Rect::new(origin, fitted)
+    }
+}
+
+/// Value of a uniform declared in the source of `Paint::Shader`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShaderUniform {
+    Float(f32),
+    /// Vectors, matrices and arrays of floats.
+    Floats(Vec<f32>),
+    /// Unpremultiplied linear RGBA, i.e. `float4`/`half4`.
+    Color(Color),
+    /// Rectangle of the shape being painted, as `float4(x, y, width, height)`.
+    Rect,
+    /// Seconds since a shader was first painted, as `float`. Frames are requested for as long as it is painted.
+    Time,
+}
+
+/// Animation state of a `Transition` node, carried over from frame to frame.
+#[derive(Clone)]
+pub struct TransitionState {
+    rect: Option<Animation>,
+    opacity: Animation,
+    current_rect: Rect,
+    current_opacity: f32,
+    exiting: bool,
+}
+
+impl Default for TransitionState {
+    fn default() -> Self {
+        TransitionState {
+            rect: None,
+            opacity: Animation::new(0f32.to_components(), Instant::now()),
+            current_rect: Rect::default(),
+            current_opacity: 0.,
+            exiting: false,
+        }
+    }
+}
+
+impl TransitionState {
+    /// The rectangle the transition is currently displayed at.
+    pub fn rect(&self) -> Rect {
+        self.current_rect
+    }
+
+    /// The opacity the transition is currently displayed with.
+    pub fn opacity(&self) -> f32 {
+        self.current_opacity
+    }
+
+    /// Moves towards `rect` (or towards transparency if exiting). Returns `true` while still in flight.
+    ///
+    /// Movement is animated relative to `origin`, the position of the parent layout, so that the parent moving isn't animated.
+    fn advance(&mut self, rect: Rect, origin: Point2, spec: &AnimationSpec, now: Instant) -> bool {
+        let offset = origin.to_vector();
+        let rect = rect.translate(-offset);
+
+        // the first rectangle is where the transition appears, rather than something to move from
+        let rect_animation = self
+            .rect
+            .get_or_insert_with(|| Animation::new(rect.to_components(), now));
+        self.current_rect =
+            Rect::from_components(rect_animation.update(rect.to_components(), spec, now))
+                .translate(offset);
+
+        let opacity = if self.exiting { 0f32 } else { 1. };
+        self.current_opacity =
+            f32::from_components(self.opacity.update(opacity.to_components(), spec, now))
+                .clamp(0., 1.);
+
+        rect_animation.is_running() || self.opacity.is_running()
+    }
+}
+
+/// Line layout of a `Node::Paragraph`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParagraphStyle {
+    pub align: sk::textlayout::TextAlign,
+    /// Height of each line as a multiple of the font size; `None` uses the line spacing of the font.
+    pub line_height: Option<f32>,
+    /// Lines past this are dropped, and the last line which is kept ends in `ellipsis` instead.
+    pub max_lines: Option<usize>,
+    pub ellipsis: String,
+}
+
+impl Default for ParagraphStyle {
+    fn default() -> Self {
+        ParagraphStyle {
+            align: sk::textlayout::TextAlign::Start,
+            line_height: None,
+            max_lines: None,
+            ellipsis: String::from("\u{2026}"),
+        }
+    }
+}
+
+/// Lines drawn along a `TextSpan`, in the color of its text.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextDecoration {
+    pub underline: bool,
+    pub overline: bool,
+    pub line_through: bool,
 }
 
+impl ToSkia<sk::textlayout::TextDecoration> for TextDecoration {
+    fn to_skia(&self) -> sk::textlayout::TextDecoration {
+        let bits =
+            self.underline as u32 | (self.overline as u32) << 1 | (self.line_through as u32) << 2;
+        // SAFETY: these are the bits of skia's `TextDecoration` enum, which skia-safe fails to declare in full as
+        // `TextDecoration::LINE_THROUGH` is mistakenly an alias of `OVERLINE` (so `from_bits_truncate` would drop line-through)
+        unsafe { sk::textlayout::TextDecoration::from_bits_unchecked(bits) }
+    }
+}
+
+pub type SpanCallback = Rc<dyn Fn(&mut Cx)>;
+
+/// A run of text within `rich_text`, with its own style.
+///
+/// Unset fields fall back to `Resources::default_font`, `Resources::fallback_text_size` and `Resources::fallback_text_fill`.
+#[derive(Clone)]
+pub struct TextSpan {
+    pub text: String,
+    /// Name of a font in `Resources::fonts`, or the family name of fonts in there (to pick between weights of a family).
+    pub font: Option<String>,
+    pub size: Option<f32>,
+    pub weight: sk::font_style::Weight,
+    pub italic: bool,
+    pub fill: Option<Paint>,
+    pub decoration: TextDecoration,
+    /// Painted behind the text of the span, line by line.
+    pub background: Option<Paint>,
+    /// Invoked when the span is clicked, e.g. to follow a hyperlink.
+    pub on_click: Option<SpanCallback>,
+}
+
+impl TextSpan {
+    pub fn new(text: impl Into<String>) -> Self {
+        TextSpan {
+            text: text.into(),
+            font: None,
+            size: None,
+            weight: sk::font_style::Weight::NORMAL,
+            italic: false,
+            fill: None,
+            decoration: Default::default(),
+            background: None,
+            on_click: None,
+        }
+    }
+
+    pub fn font(mut self, font: impl Into<String>) -> Self {
+        self.font = Some(font.into());
+        self
+    }
+
+    pub fn size(mut self, size: f32) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    /// Requests a weight from the font family; weights which haven't been loaded resolve to the closest available one.
+    pub fn weight(mut self, weight: impl Into<sk::font_style::Weight>) -> Self {
+        self.weight = weight.into();
+        self
+    }
+
+    pub fn bold(self) -> Self {
+        self.weight(sk::font_style::Weight::BOLD)
+    }
+
+    pub fn italic(mut self) -> Self {
+        self.italic = true;
+        self
+    }
+
+    pub fn fill(mut self, fill: Paint) -> Self {
+        self.fill = Some(fill);
+        self
+    }
+
+    pub fn decoration(mut self, decoration: TextDecoration) -> Self {
+        self.decoration = decoration;
+        self
+    }
+
+    pub fn underline(mut self) -> Self {
+        self.decoration.underline = true;
+        self
+    }
+
+    pub fn line_through(mut self) -> Self {
+        self.decoration.line_through = true;
+        self
+    }
+
+    pub fn background(mut self, background: Paint) -> Self {
+        self.background = Some(background);
+        self
+    }
+
+    pub fn on_click(mut self, on_click: impl Fn(&mut Cx) + 'static) -> Self {
+        self.on_click = Some(Rc::new(on_click));
+        self
+    }
+
+    /// Returns `true` if both spans are shaped and painted the same, regardless of `on_click`.
+    pub fn looks_like(&self, other: &TextSpan) -> bool {
+        self.text == other.text
+            && self.font == other.font
+            && self.size == other.size
+            && self.weight == other.weight
+            && self.italic == other.italic
+            && self.fill == other.fill
+            && self.decoration == other.decoration
+            && self.background == other.background
+    }
+}
+
+/// Returns the range of each span within the text of the paragraph they form, in UTF-16 code units (as Skia counts them).
+pub(crate) fn span_ranges(spans: &[TextSpan]) -> Vec<Range<usize>> {
+    let mut start = 0;
+    spans
+        .iter()
+        .map(|span| {
+            let end = start + span.text.encode_utf16().count();
+            let range = start..end;
+            start = end;
+            range
+        })
+        .collect()
+}
+
+/// Returns the index of the range of `ranges` (see `span_ranges`) whose text is under `pos`, relative to the top-left corner of `paragraph`.
+fn range_index_at(
+    paragraph: &sk::textlayout::Paragraph,
+    ranges: &[Range<usize>],
+    pos: Point2,
+) -> Option<usize> {
+    ranges.iter().position(|range| {
+        paragraph
+            .get_rects_for_range(
+                range.clone(),
+                sk::textlayout::RectHeightStyle::Max,
+                sk::textlayout::RectWidthStyle::Tight,
+            )
+            .iter()
+            .any(|text_box| {
+                let r = text_box.rect;
+                Rect::new(point2(r.left, r.top), size2(r.width(), r.height())).contains(pos)
+            })
+    })
+}
+
+/// Box shadow of a `Rectangle`, following its rounded corners without affecting its size.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shadow {
+    pub offset: Point2,
+    /// Blur radius, where the shadow fades out over roughly this distance.
+    pub blur: f32,
+    /// Distance the shadow is grown (or shrunk if negative) by before blurring.
+    pub spread: f32,
+    pub color: Color,
+    /// Whether the shadow is cast inwards, within the rectangle.
+    pub inset: bool,
+}
+
+impl Shadow {
+    pub fn outer(offset: Point2, blur: f32, spread: f32, color: Color) -> Self {
+        Shadow {
+            offset,
+            blur,
+            spread,
+            color,
+            inset: false,
+        }
+    }
+
+    pub fn inset(offset: Point2, blur: f32, spread: f32, color: Color) -> Self {
+        Shadow {
+            offset,
+            blur,
+            spread,
+            color,
+            inset: true,
+        }
+    }
+
+    /// Returns the area painted by this shadow when cast by `rect`, which contains inset shadows.
+    pub fn bounds(&self, rect: Rect) -> Rect {
+        if self.inset {
+            rect
+        } else {
+            // the blur fades out well within twice its radius
+            let extent = (self.spread + self.blur * 2.).max(0.);
+            rect.inflate(extent, extent)
+                .translate(self.offset.to_vector())
+        }
+    }
+}
+
+/// Determines which areas of a self-intersecting path are inside it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FillRule {
+    NonZero,
+    EvenOdd,
+}
+
+/// Vector shape for `Node::Path`, built from drawing commands or parsed from SVG path data.
+#[derive(Clone, Default, PartialEq)]
+pub struct Path {
+    path: sk::Path,
+}
+
+impl Path {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Parses SVG path data (i.e. the `d` attribute of `<path>`).
+    pub fn from_svg(data: &str) -> Option<Self> {
+        sk::Path::from_svg(data).map(|path| Path { path })
+    }
+
+    pub fn move_to(mut self, point: Point2) -> Self {
+        self.path.move_to(point.to_skia());
+        self
+    }
+
+    pub fn line_to(mut self, point: Point2) -> Self {
+        self.path.line_to(point.to_skia());
+        self
+    }
+
+    pub fn quad_to(mut self, control: Point2, point: Point2) -> Self {
+        self.path.quad_to(control.to_skia(), point.to_skia());
+        self
+    }
+
+    pub fn cubic_to(mut self, control_a: Point2, control_b: Point2, point: Point2) -> Self {
+        self.path
+            .cubic_to(control_a.to_skia(), control_b.to_skia(), point.to_skia());
+        self
+    }
+
+    /// Adds an elliptical arc to `point`, following the semantics of the SVG `A` command.
+    ///
+    /// `rotation` is in degrees.
+    pub fn arc_to(
+        mut self,
+        radii: Size2,
+        rotation: f32,
+        large_arc: bool,
+        sweep: bool,
+        point: Point2,
+    ) -> Self {
+        self.path.arc_to_rotated(
+            sk::Point::new(radii.width, radii.height),
+            rotation,
+            if large_arc {
+                sk::path::ArcSize::Large
+            } else {
+                sk::path::ArcSize::Small
+            },
+            if sweep {
+                sk::PathDirection::CW
+            } else {
+                sk::PathDirection::CCW
+            },
+            point.to_skia(),
+        );
+        self
+    }
+
+    pub fn close(mut self) -> Self {
+        self.path.close();
+        self
+    }
+
+    pub fn fill_rule(mut self, fill_rule: FillRule) -> Self {
+        self.path.set_fill_type(match fill_rule {
+            FillRule::NonZero => sk::PathFillType::Winding,
+            FillRule::EvenOdd => sk::PathFillType::EvenOdd,
+        });
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stroke {
+    pub paint: Paint,
+    pub width: f32,
+    pub cap: sk::PaintCap,
+    pub join: sk::PaintJoin,
+    /// Alternating lengths of dashes and gaps; empty for a solid stroke.
+    pub dash: Vec<f32>,
+    pub dash_offset: f32,
+}
+
+impl Stroke {
+    pub fn new(paint: Paint, width: f32) -> Self {
+        Stroke {
+            paint,
+            width,
+            cap: sk::PaintCap::Butt,
+            join: sk::PaintJoin::Miter,
+            dash: Vec::new(),
+            dash_offset: 0.,
+        }
+    }
+
+    /// Applies the stroke style (everything but the paint itself) to `paint`.
+    pub(crate) fn apply(&self, paint: &mut sk::Paint) {
+        paint.set_style(sk::PaintStyle::Stroke);
+        paint.set_stroke_width(self.width);
+        paint.set_stroke_cap(self.cap);
+        paint.set_stroke_join(self.join);
+        if !self.dash.is_empty() {
+            paint.set_path_effect(sk::PathEffect::dash(&self.dash, self.dash_offset));
+        }
+    }
+
+    /// Returns everything but the paint, which determines the area covered by the stroke.
+    pub(crate) fn geometry(&self) -> (f32, sk::PaintCap, sk::PaintJoin, &[f32], f32) {
+        (
+            self.width,
+            self.cap,
+            self.join,
+            &self.dash,
+            self.dash_offset,
+        )
+    }
+
+    /// Returns the area covered by stroking `path`.
+    fn outline(&self, path: &sk::Path) -> Option<sk::Path> {
+        let mut paint = sk::Paint::default();
+        self.apply(&mut paint);
+        paint.get_fill_path(path, None, None)
+    }
+}
+
+/// Where a `Layer` node is drawn (and hit tested) relative to the rest of the tree.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum ZOrder {
+    /// Drawn in its own layer beneath everything else.
     Bottom,
+    /// Drawn above its parent and preceding siblings (the default painter's order).
     Above,
+    /// Drawn beneath its parent and its siblings.
     Below,
+    /// Drawn in its own layer above everything else, receiving input first.
     Top,
 }
 
@@ -38,6 +710,9 @@ impl Default for ZOrder {
     }
 }
 
+/// Identity of a keyed node, used to match children across frames regardless of their index.
+pub type Key = u64;
+
 #[derive(Clone)]
 pub enum Node {
     Null,
@@ -51,8 +726,33 @@ pub enum Node {
         child: Box<Node>,
         callback: Rc<dyn Fn(&mut Cx, &mut ResolvedNode)>,
     },
+    Keyed {
+        key: Key,
+        child: Box<Node>,
+    },
+    Layer {
+        z_order: ZOrder,
+        child: Box<Node>,
+    },
+    Clip {
+        corner_radius: [f32; 4],
+        child: Box<Node>,
+    },
+    Transform {
+        transform: Transform2,
+        origin: Point2,
+        child: Box<Node>,
+    },
+    Effect {
+        effect: Effect,
+        child: Box<Node>,
+    },
+    Transition {
+        spec: AnimationSpec,
+        child: Box<Node>,
+    },
     Layout {
-        layout: Rc<dyn Layout>,
+        layout: Rc<dyn BoxLayout>,
         children: Vec<Node>,
     },
     Text {
@@ -61,12 +761,39 @@ pub enum Node {
         size: Option<f32>,
         fill: Option<Paint>,
     },
+    /// Text which wraps to the available width, see `ParagraphStyle`.
+    Paragraph {
+        text: String,
+        font: String,
+        size: Option<f32>,
+        fill: Option<Paint>,
+        style: ParagraphStyle,
+    },
+    /// Differently styled spans shaped together as one paragraph.
+    RichText {
+        spans: Vec<TextSpan>,
+        style: ParagraphStyle,
+    },
     Rectangle {
         size: Size2,
         corner_radius: [f32; 4],
         background: Option<Paint>,
         border: f32,
         border_fill: Option<Paint>,
+        shadows: Vec<Shadow>,
+    },
+    Path {
+        path: Path,
+        fill: Option<Paint>,
+        stroke: Option<Stroke>,
+    },
+    Image {
+        image: Rc<Image>,
+        /// Defaults to the natural size of the image.
+        size: Option<Size2>,
+        fit: ImageFit,
+        align: Point2,
+        quality: sk::FilterQuality,
     },
     Draw {
         size: Size2,
@@ -103,6 +830,63 @@ impl Node {
                     callback: Rc::clone(callback),
                 }))
             }
+            Node::Keyed { key, child } => {
+                let child = child.resolve(resources)?.ok_or(Error::EmptyNode)?;
+                Ok(Some(ResolvedNode::Keyed {
+                    rect: Rect::new(Default::default(), child.size()),
+                    child: Box::new(child),
+                    key: *key,
+                }))
+            }
+            Node::Layer { z_order, child } => {
+                let child = child.resolve(resources)?.ok_or(Error::EmptyNode)?;
+                Ok(Some(ResolvedNode::Layer {
+                    rect: Rect::new(Default::default(), child.size()),
+                    child: Box::new(child),
+                    z_order: *z_order,
+                }))
+            }
+            Node::Clip {
+                corner_radius,
+                child,
+            } => {
+                let child = child.resolve(resources)?.ok_or(Error::EmptyNode)?;
+                Ok(Some(ResolvedNode::Clip {
+                    rect: Rect::new(Default::default(), child.size()),
+                    child: Box::new(child),
+                    corner_radii: *corner_radius,
+                }))
+            }
+            Node::Transform {
+                transform,
+                origin,
+                child,
+            } => {
+                let child = child.resolve(resources)?.ok_or(Error::EmptyNode)?;
+                Ok(Some(ResolvedNode::Transform {
+                    rect: Rect::new(Default::default(), child.size()),
+                    child: Box::new(child),
+                    transform: *transform,
+                    origin: *origin,
+                }))
+            }
+            Node::Effect { effect, child } => {
+                let child = child.resolve(resources)?.ok_or(Error::EmptyNode)?;
+                Ok(Some(ResolvedNode::Effect {
+                    rect: Rect::new(Default::default(), child.size()),
+                    child: Box::new(child),
+                    effect: effect.clone(),
+                }))
+            }
+            Node::Transition { spec, child } => {
+                let child = child.resolve(resources)?.ok_or(Error::EmptyNode)?;
+                Ok(Some(ResolvedNode::Transition {
+                    rect: Rect::new(Default::default(), child.size()),
+                    child: Box::new(child),
+                    spec: *spec,
+                    state: TransitionState::default(),
+                }))
+            }
             Node::Layout { layout, children } => {
                 let children = children
                     .iter()
@@ -111,16 +895,15 @@ impl Node {
                     .into_iter()
                     .filter_map(|x| x)
                     .collect::<Vec<_>>();
-                let size = layout.size(
-                    &children
-                        .iter()
-                        .map(|child| child.size())
-                        .collect::<Vec<_>>(),
-                );
+                // the size of a layout is only known once it is given constraints
                 Ok(Some(ResolvedNode::Layout {
                     layout: Rc::clone(layout),
                     children,
-                    rect: Rect::new(Default::default(), size),
+                    exiting: Vec::new(),
+                    baseline: None,
+                    rect: Rect::default(),
+                    cache: Default::default(),
+                    stale: false,
                 }))
             }
             Node::Text {
@@ -130,50 +913,34 @@ impl Node {
                 fill,
             } => {
                 let size = size.unwrap_or_else(|| resources.fallback_text_size);
-                let font_data = Rc::clone(&resources.fonts[font]);
-
-                let fnt = resources
-                    .font_cache
-                    .entry((font.clone(), OrderedFloat(size)))
-                    .or_insert_with(|| {
-                        let mut font = sk::Font::new(&font_data.sk, size);
-                        font.set_hinting(sk::FontHinting::Normal).set_subpixel(true);
-                        Rc::new(font)
-                    })
-                    .clone();
-
-                let (blob, bounds) = if !text.is_empty() {
-                    let (blob, bounds) = resources
-                        .shaper_cache
-                        .entry((text.clone(), font.clone(), OrderedFloat(size)))
-                        .or_insert_with(|| {
-                            let mut text_blob_builder_run_handler =
-                                sk::shaper::TextBlobBuilderRunHandler::new(
-                                    &text,
-                                    sk::Point::default(),
-                                );
-
-                            let shaper = sk::Shaper::new(None);
-
-                            shaper.shape(
-                                &text,
-                                &fnt,
-                                true,
-                                std::f32::MAX,
-                                &mut text_blob_builder_run_handler,
-                            );
-
-                            let blob = text_blob_builder_run_handler.make_blob().unwrap();
-                            let bounds = fnt.measure_str(&text, None).1;
-                            let bounds = size2(bounds.width(), fnt.spacing());
-
-                            (blob, bounds)
-                        })
-                        .clone();
-
-                    (Some(blob), bounds)
-                } else {
+                let chain = resources.font_chain(font)?;
+                let font_data = Rc::clone(&resources.fonts[&chain[0]]);
+                let fnt = resources.sk_font(&chain[0], size);
+
+                let key = (text.clone(), font.clone(), OrderedFloat(size));
+                let (blob, bounds) = if text.is_empty() {
                     (None, size2(0., 0.))
+                } else if let Some((blob, bounds)) = resources.shaper_cache.get(&key) {
+                    (Some(blob.clone()), *bounds)
+                } else {
+                    // every run sits on the baseline of the primary font, whichever font it falls back to
+                    let mut run_handler =
+                        FallbackRunHandler::new(sk::Point::new(0., -fnt.metrics().1.ascent));
+                    let shaper = sk::Shaper::new(None);
+
+                    for (range, run_font) in resources.fallback_runs(text, &chain) {
+                        let run_font = resources.sk_font(&run_font, size);
+                        shaper.shape(&text[range], &run_font, true, f32::MAX, &mut run_handler);
+                    }
+
+                    let bounds = size2(run_handler.pen.x, fnt.spacing());
+                    match run_handler.builder.make() {
+                        Some(blob) => {
+                            resources.shaper_cache.insert(key, (blob.clone(), bounds));
+                            (Some(blob), bounds)
+                        }
+                        None => (None, bounds),
+                    }
                 };
 
                 Ok(Some(ResolvedNode::Text {
@@ -189,18 +956,80 @@ impl Node {
                     rect: Rect::new(Default::default(), bounds),
                 }))
             }
+            Node::Paragraph {
+                text,
+                font,
+                size,
+                fill,
+                style,
+            } => {
+                let size = size.unwrap_or(resources.fallback_text_size);
+                let fill = fill
+                    .clone()
+                    .unwrap_or_else(|| resources.fallback_text_fill.clone());
+                let paragraph = resources.paragraph(text, font, size, &fill, style);
+
+                Ok(Some(ResolvedNode::Paragraph {
+                    text: text.clone(),
+                    font: font.clone(),
+                    size,
+                    fill,
+                    style: style.clone(),
+                    paragraph: Rc::new(RefCell::new(paragraph)),
+                    rect: Rect::default(),
+                }))
+            }
+            Node::RichText { spans, style } => {
+                let paragraph = resources.rich_paragraph(spans, style);
+                Ok(Some(ResolvedNode::RichText {
+                    spans: spans.clone(),
+                    style: style.clone(),
+                    paragraph: Rc::new(RefCell::new(paragraph)),
+                    rect: Rect::default(),
+                }))
+            }
             Node::Rectangle {
                 size,
                 corner_radius,
                 background,
                 border,
                 border_fill,
+                shadows,
             } => Ok(Some(ResolvedNode::Rectangle {
                 rect: Rect::new(Default::default(), *size),
                 corner_radii: *corner_radius,
                 background: background.clone(),
                 border: *border,
                 border_fill: border_fill.clone(),
+                shadows: shadows.clone(),
+            })),
+            Node::Path { path, fill, stroke } => {
+                let (sk_path, outline, size) = resolve_path(path, stroke.as_ref());
+                Ok(Some(ResolvedNode::Path {
+                    path: path.clone(),
+                    sk_path,
+                    outline,
+                    fill: fill.clone(),
+                    stroke: stroke.clone(),
+                    rect: Rect::new(Default::default(), size),
+                }))
+            }
+            Node::Image {
+                image,
+                size,
+                fit,
+                align,
+                quality,
+            } => Ok(Some(ResolvedNode::Image {
+                sk_image: resources.image(image)?,
+                rect: Rect::new(
+                    Default::default(),
+                    size.unwrap_or_else(|| size2(image.width() as _, image.height() as _)),
+                ),
+                image: Rc::clone(image),
+                fit: *fit,
+                align: *align,
+                quality: *quality,
             })),
             Node::Draw { size, draw_fn } => Ok(Some(ResolvedNode::Draw {
                 rect: Rect::new(Default::default(), *size),
@@ -212,7 +1041,11 @@ impl Node {
 
     pub fn children(&self) -> Vec<&Node> {
         match self {
-            Node::Interact { child, .. } => vec![child.as_ref()],
+            Node::Interact { child, .. }
+            | Node::Keyed { child, .. }
+            | Node::Layer { child, .. } => {
+                vec![child.as_ref()]
+            }
             Node::Layout { children, .. } => children.iter().collect(),
             _ => vec![],
         }
@@ -241,30 +1074,125 @@ impl IntoNode for Node {
     }
 }
 
-pub fn iff<N: IntoNode>(cond: bool, f: impl FnOnce() -> N) -> Node {
-    if cond {
-        f().into_node()
-    } else {
-        null()
+pub fn iff<N: IntoNode>(cond: bool, f: impl FnOnce() -> N) -> Node {
+    if cond {
+        f().into_node()
+    } else {
+        null()
+    }
+}
+
+pub fn null() -> Node {
+    Node::Null
+}
+
+/// Invokes `callback` with the input received by `child`.
+///
+/// With `passthrough`, input is only received where something within `child` handles it itself (such as a clickable span of `rich_text`), and passes through to whatever is underneath everywhere else.
+#[track_caller]
+pub fn interact(
+    child: impl IntoNode,
+    callback: impl Fn(&mut Cx, &Interaction) + 'static,
+    passthrough: bool,
+) -> Node {
+    call(move || Node::Interact {
+        child: Box::new(child.into_node()),
+        callback: Rc::new(callback),
+        id: Id::current(),
+        passthrough,
+    })
+}
+
+/// Wraps the node returned by `f` with a `key`, so that it is matched by key rather than by index when its parent layout is diffed.
+///
+/// `f` is also invoked within a scope unique to `key`, meaning state and interaction IDs created inside it stay attached to the key as siblings are inserted, removed or reordered.
+///
+/// Keys should be unique among siblings. Siblings which share a key are matched in order instead, as if they weren't keyed.
+#[track_caller]
+pub fn keyed<K, N>(key: &K, f: impl FnOnce() -> N) -> Node
+where
+    K: Send + std::hash::Hash + Clone + Eq + 'static,
+    N: IntoNode,
+{
+    Node::Keyed {
+        key: fxhash::hash64(key),
+        child: Box::new(crate::call_unique(key, move || f().into_node())),
+    }
+}
+
+/// Invokes `callback` with the resolved node once layout has been performed, i.e. once its `rect` is final.
+///
+/// The rectangle is in layout space, unaffected by any `Transform` nodes.
+pub fn capture(
+    child: impl IntoNode,
+    callback: impl Fn(&mut Cx, &mut ResolvedNode) + 'static,
+) -> Node {
+    Node::Capture {
+        child: Box::new(child.into_node()),
+        callback: Rc::new(callback),
+    }
+}
+
+/// Places `child` in the given `z_order`, see `ZOrder`.
+pub fn layer(z_order: ZOrder, child: impl IntoNode) -> Node {
+    Node::Layer {
+        z_order,
+        child: Box::new(child.into_node()),
+    }
+}
+
+/// Clips the rendering and hit testing of `child` to its rectangle, rounded by `corner_radius`.
+///
+/// `ZOrder::Top` and `ZOrder::Bottom` layers within `child` are not clipped.
+pub fn clip(corner_radius: [f32; 4], child: impl IntoNode) -> Node {
+    Node::Clip {
+        corner_radius,
+        child: Box::new(child.into_node()),
+    }
+}
+
+/// Applies `transform` to the rendering and hit testing of `child`.
+///
+/// `origin` is relative to the size of the node, e.g. `(0.5, 0.5)` rotates/scales around the center.
+/// The transform does not affect layout.
+pub fn transform(transform: Transform2, origin: Point2, child: impl IntoNode) -> Node {
+    Node::Transform {
+        transform,
+        origin,
+        child: Box::new(child.into_node()),
+    }
+}
+
+/// Renders `child` with a group `effect`.
+///
+/// `ZOrder::Top` and `ZOrder::Bottom` layers within `child` are not affected.
+pub fn effect(effect: Effect, child: impl IntoNode) -> Node {
+    Node::Effect {
+        effect,
+        child: Box::new(child.into_node()),
     }
 }
 
-pub fn null() -> Node {
-    Node::Null
+/// Renders `child` with an overall `opacity`, as opposed to the opacity of each individual paint.
+pub fn opacity(opacity: f32, child: impl IntoNode) -> Node {
+    effect(
+        Effect {
+            opacity,
+            ..Default::default()
+        },
+        child,
+    )
 }
 
-#[track_caller]
-pub fn interact(
-    child: impl IntoNode,
-    callback: impl Fn(&mut Cx, &Interaction) + 'static,
-    passthrough: bool,
-) -> Node {
-    call(move || Node::Interact {
+/// Smoothly animates `child` from where it was laid out in the previous frame to where it is laid out now, as described by `spec`.
+///
+/// `child` is faded in when it first appears. When a keyed transition (i.e. `keyed(key, || transition(...))`) is removed from a layout, it is faded out in place, and fades back in if its key returns before it has disappeared.
+/// Movement is applied as a transform, so `child` itself is only ever laid out at its final rectangle. Only movement within the parent layout is animated; moving along with the parent (e.g. while scrolling) is not.
+pub fn transition(spec: AnimationSpec, child: impl IntoNode) -> Node {
+    Node::Transition {
+        spec,
         child: Box::new(child.into_node()),
-        callback: Rc::new(callback),
-        id: Id::current(),
-        passthrough,
-    })
+    }
 }
 
 pub fn text(text: impl Into<String>) -> Node {
@@ -290,6 +1218,141 @@ pub fn styled_text(
     }
 }
 
+/// Text which wraps to the width it is laid out within.
+pub fn paragraph(text: impl Into<String>) -> Node {
+    Node::Paragraph {
+        text: text.into(),
+        font: String::from("sans-serif"),
+        size: None,
+        fill: None,
+        style: Default::default(),
+    }
+}
+
+/// Shapes `spans` together as one paragraph, such that lines break across spans.
+///
+/// Clicks on spans with an `on_click` handler (i.e. the mouse being pressed and released over the same span) are dispatched to that handler.
+/// Input anywhere else passes through to whatever is underneath.
+pub fn rich_text(spans: Vec<TextSpan>, style: ParagraphStyle) -> Node {
+    let clickable = spans.iter().any(|span| span.on_click.is_some());
+    let ranges = span_ranges(&spans);
+    let handlers = spans
+        .iter()
+        .map(|span| span.on_click.clone())
+        .collect::<Vec<_>>();
+    let node = Node::RichText { spans, style };
+    if !clickable {
+        return node;
+    }
+
+    // spans can only be hit tested once laid out, so the paragraph and where it was laid out are kept for the interaction callback
+    let laid_out = Rc::new(RefCell::new(None));
+    let captured = Rc::clone(&laid_out);
+    // index of the span the mouse was pressed over
+    let pressed = Rc::new(std::cell::Cell::new(None));
+    interact(
+        capture(node, move |_, node| {
+            *captured.borrow_mut() = match node.children().first() {
+                Some(ResolvedNode::RichText {
+                    paragraph, rect, ..
+                }) => Some((Rc::clone(paragraph), *rect)),
+                _ => None,
+            };
+        }),
+        move |cx, event| {
+            let span_at = |pos: Point2| {
+                laid_out.borrow().as_ref().and_then(|(paragraph, rect)| {
+                    range_index_at(&paragraph.borrow(), &ranges, pos - rect.origin.to_vector())
+                })
+            };
+
+            match event {
+                Interaction::MouseDown {
+                    button: MouseButton::Left,
+                    pos,
+                    ..
+                } => pressed.set(span_at(*pos)),
+                Interaction::MouseUp {
+                    button: MouseButton::Left,
+                    pos,
+                    ..
+                } => {
+                    let index = span_at(*pos);
+                    if index.is_some() && pressed.take() == index {
+                        if let Some(on_click) = index.and_then(|index| handlers[index].clone()) {
+                            on_click(cx);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        },
+        true,
+    )
+}
+
+pub fn styled_paragraph(
+    text: impl Into<String>,
+    font: impl Into<String>,
+    size: impl Into<Option<f32>>,
+    fill: impl Into<Option<Paint>>,
+    style: ParagraphStyle,
+) -> Node {
+    Node::Paragraph {
+        text: text.into(),
+        font: font.into(),
+        size: size.into(),
+        fill: fill.into(),
+        style,
+    }
+}
+
+/// Draws `path` sized to its bounds, i.e. translated such that its top-left-most point is at the origin of the node.
+pub fn path(path: Path, fill: impl Into<Option<Paint>>, stroke: impl Into<Option<Stroke>>) -> Node {
+    Node::Path {
+        path,
+        fill: fill.into(),
+        stroke: stroke.into(),
+    }
+}
+
+/// Returns `path` and the outline of its stroke, offset together so that both lie at the origin, along with their size.
+pub(crate) fn resolve_path(
+    path: &Path,
+    stroke: Option<&Stroke>,
+) -> (sk::Path, Option<sk::Path>, Size2) {
+    let outline = stroke.and_then(|stroke| stroke.outline(&path.path));
+    let bounds = path_bounds(&path.path, outline.as_ref());
+
+    let offset = (-bounds.left, -bounds.top);
+    (
+        path.path.with_offset(offset),
+        outline.map(|outline| outline.with_offset(offset)),
+        size2(bounds.width(), bounds.height()),
+    )
+}
+
+/// Returns the bounds of `path` together with the outline of its stroke.
+pub(crate) fn path_bounds(path: &sk::Path, outline: Option<&sk::Path>) -> sk::Rect {
+    // the outline includes miters and caps, which may stick out further than half the stroke width
+    let mut bounds = path.compute_tight_bounds();
+    if let Some(outline) = outline {
+        bounds.join(outline.compute_tight_bounds());
+    }
+    bounds
+}
+
+/// Draws `image` centered within `size` (or at its natural size) according to `fit`.
+pub fn image(image: Rc<Image>, size: impl Into<Option<Size2>>, fit: ImageFit) -> Node {
+    Node::Image {
+        image,
+        size: size.into(),
+        fit,
+        align: point2(0.5, 0.5),
+        quality: sk::FilterQuality::Medium,
+    }
+}
+
 pub fn rectangle(
     size: Size2,
     corner_radius: [f32; 4],
@@ -303,6 +1366,26 @@ pub fn rectangle(
         background: background.into(),
         border,
         border_fill: border_fill.into(),
+        shadows: Vec::new(),
+    }
+}
+
+/// Same as `rectangle`, with `shadows` drawn in order (outer shadows beneath the background, inset shadows above it).
+pub fn shadowed_rectangle(
+    size: Size2,
+    corner_radius: [f32; 4],
+    background: impl Into<Option<Paint>>,
+    border: f32,
+    border_fill: impl Into<Option<Paint>>,
+    shadows: Vec<Shadow>,
+) -> Node {
+    Node::Rectangle {
+        size,
+        corner_radius,
+        background: background.into(),
+        border,
+        border_fill: border_fill.into(),
+        shadows,
     }
 }
 
@@ -313,6 +1396,7 @@ pub fn draw(size: Size2, draw_fn: impl Fn(Rect, &mut Cx, &mut sk::Canvas) + 'sta
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum MouseButton {
     Left,
     Middle,
@@ -321,6 +1405,7 @@ pub enum MouseButton {
 
 pub type KeyCode = winit::event::VirtualKeyCode;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Interaction {
     MouseDown {
         button: MouseButton,
@@ -341,6 +1426,12 @@ pub enum Interaction {
     CursorMove {
         pos: Point2,
     },
+    /// Bubbles up from the innermost interact node under the cursor until a handler calls `Cx::stop_propagation`.
+    Scroll {
+        delta: Point2,
+        pos: Point2,
+        modifiers: winit::event::ModifiersState,
+    },
     GainFocus,
     LoseFocus,
     ReceiveCharacter {
@@ -360,13 +1451,209 @@ impl Interaction {
     pub fn is_mouse_down(&self) -> bool {
         matches!(self, Interaction::MouseDown { .. })
     }
+
+    /// Returns this event with its positions (and scroll delta) mapped by `transform`.
+    pub fn transformed(&self, transform: &Transform2) -> Interaction {
+        let mut event = *self;
+        match &mut event {
+            Interaction::MouseDown { pos, .. }
+            | Interaction::MouseUp { pos, .. }
+            | Interaction::CursorEnter { pos }
+            | Interaction::CursorExit { pos }
+            | Interaction::CursorMove { pos } => *pos = transform.transform_point(*pos),
+            Interaction::Scroll { delta, pos, .. } => {
+                *delta = transform.transform_vector(delta.to_vector()).to_point();
+                *pos = transform.transform_point(*pos);
+            }
+            _ => {}
+        }
+        event
+    }
 }
 
+/// Bottom-up layout, where the size of a node is derived from the natural sizes of its children, which are then positioned within the final rectangle.
+///
+/// Every `Layout` is also a `BoxLayout`; children are measured without constraints, then laid out again to fit the rectangles given by `position`.
 pub trait Layout {
     fn size(&self, sizes: &[Size2]) -> Size2;
     fn position(&self, rect: Rect, sizes: &[Size2]) -> Vec<Rect>;
 }
 
+/// Applies `transform` about `origin`, which is relative to the size of `rect` (see `Node::Transform`).
+pub(crate) fn transform_about(transform: &Transform2, origin: Point2, rect: &Rect) -> Transform2 {
+    let origin =
+        rect.origin + point2(origin.x * rect.size.width, origin.y * rect.size.height).to_vector();
+    Transform2::translation(-origin.x, -origin.y)
+        .then(transform)
+        .then_translate(origin.to_vector())
+}
+
+/// Range of sizes a node may take, passed down from its parent during layout.
+///
+/// Either component of `max` may be infinite, in which case the node takes its natural size along that axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoxConstraints {
+    pub min: Size2,
+    pub max: Size2,
+}
+
+impl BoxConstraints {
+    pub fn new(min: Size2, max: Size2) -> Self {
+        BoxConstraints { min, max }
+    }
+
+    /// Only allows exactly `size`.
+    pub fn tight(size: Size2) -> Self {
+        BoxConstraints::new(size, size)
+    }
+
+    /// Allows anything from nothing up to `max`.
+    pub fn loose(max: Size2) -> Self {
+        BoxConstraints::new(size2(0., 0.), max)
+    }
+
+    /// Allows any size at all.
+    pub fn unbounded() -> Self {
+        BoxConstraints::loose(size2(f32::INFINITY, f32::INFINITY))
+    }
+
+    /// Removes the minimum size.
+    pub fn loosen(self) -> Self {
+        BoxConstraints::loose(self.max)
+    }
+
+    /// Shrinks the constraints by `sides` (e.g. a margin), down to nothing.
+    pub fn deflate(self, sides: Sides2) -> Self {
+        let deflate = |size: Size2| {
+            size2(
+                (size.width - sides.horizontal()).max(0.),
+                (size.height - sides.vertical()).max(0.),
+            )
+        };
+        BoxConstraints::new(deflate(self.min), deflate(self.max))
+    }
+
+    /// Returns the size closest to `size` which satisfies these constraints.
+    pub fn constrain(&self, size: Size2) -> Size2 {
+        size2(
+            size.width.max(self.min.width).min(self.max.width),
+            size.height.max(self.min.height).min(self.max.height),
+        )
+    }
+
+    pub fn is_tight(&self) -> bool {
+        self.min == self.max
+    }
+
+    pub fn has_bounded_width(&self) -> bool {
+        self.max.width.is_finite()
+    }
+
+    pub fn has_bounded_height(&self) -> bool {
+        self.max.height.is_finite()
+    }
+}
+
+impl Default for BoxConstraints {
+    fn default() -> Self {
+        BoxConstraints::unbounded()
+    }
+}
+
+/// Results of laying out a `Layout` node within each of the constraints it was given during the current layout pass.
+///
+/// Layouts commonly lay out their children more than once (e.g. to measure them first), which would otherwise repeat for every level of the tree.
+/// `ResolvedNode::perform_layout` clears the caches of the whole tree before laying it out.
+#[derive(Clone, Default)]
+pub struct LayoutCache {
+    entries: Vec<LayoutCacheEntry>,
+}
+
+#[derive(Clone)]
+struct LayoutCacheEntry {
+    constraints: BoxConstraints,
+    size: Size2,
+    baseline: Option<f32>,
+    /// Rectangles of the descendants, in pre-order (see `ResolvedNode::save_geometry`).
+    geometry: Vec<(Rect, Option<f32>)>,
+}
+
+impl LayoutCache {
+    /// Puts `children` back where they were laid out within `constraints`, returning the size they were laid out at.
+    fn restore(
+        &mut self,
+        constraints: BoxConstraints,
+        children: &mut [ResolvedNode],
+        baseline: &mut Option<f32>,
+    ) -> Option<Size2> {
+        let entry = self
+            .entries
+            .iter()
+            .find(|entry| entry.constraints == constraints)?;
+        let mut geometry = entry.geometry.iter().copied();
+        for child in children {
+            child.restore_geometry(&mut geometry);
+        }
+        *baseline = entry.baseline;
+        Some(entry.size)
+    }
+
+    fn store(
+        &mut self,
+        constraints: BoxConstraints,
+        size: Size2,
+        baseline: Option<f32>,
+        children: &[ResolvedNode],
+    ) {
+        let mut geometry = Vec::new();
+        for child in children {
+            child.save_geometry(&mut geometry);
+        }
+        self.entries.push(LayoutCacheEntry {
+            constraints,
+            size,
+            baseline,
+            geometry,
+        });
+    }
+}
+
+/// Top-down layout, where parents pass constraints down to their children and children pick a size within them.
+pub trait BoxLayout {
+    /// Lays out `children` within `constraints`, returning the size of this node.
+    ///
+    /// Each child is sized with `ResolvedNode::layout`, then placed relative to the top-left corner of this node with `ResolvedNode::set_position`.
+    fn layout(&self, constraints: BoxConstraints, children: &mut [ResolvedNode]) -> Size2;
+
+    /// Returns the first baseline of this node, measured from its top, once `layout` has placed `children`.
+    ///
+    /// Defaults to the first baseline found among `children`, in order.
+    fn baseline(&self, children: &[ResolvedNode]) -> Option<f32> {
+        children
+            .iter()
+            .find_map(|child| Some(child.position().y + child.baseline()?))
+    }
+}
+
+impl<L: Layout> BoxLayout for L {
+    fn layout(&self, constraints: BoxConstraints, children: &mut [ResolvedNode]) -> Size2 {
+        let sizes = children
+            .iter_mut()
+            .map(|child| child.layout(BoxConstraints::unbounded()))
+            .collect::<Vec<_>>();
+
+        let size = constraints.constrain(self.size(&sizes));
+
+        let rects = self.position(Rect::new(Point2::zero(), size), &sizes);
+        for (rect, child) in rects.into_iter().zip(children) {
+            child.layout(BoxConstraints::tight(rect.size));
+            child.set_position(rect.origin);
+        }
+
+        size
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct ShapedGlyph {
     pub id: u16,
@@ -392,9 +1679,64 @@ pub enum ResolvedNode {
         callback: Rc<dyn Fn(&mut Cx, &mut ResolvedNode)>,
         rect: Rect,
     },
+    Keyed {
+        key: Key,
+        child: Box<ResolvedNode>,
+        rect: Rect,
+    },
+    Layer {
+        z_order: ZOrder,
+        child: Box<ResolvedNode>,
+        rect: Rect,
+    },
+    Clip {
+        corner_radii: [f32; 4],
+        child: Box<ResolvedNode>,
+        rect: Rect,
+    },
+    Transform {
+        transform: Transform2,
+        origin: Point2,
+        child: Box<ResolvedNode>,
+        rect: Rect,
+    },
+    Effect {
+        effect: Effect,
+        child: Box<ResolvedNode>,
+        rect: Rect,
+    },
+    Transition {
+        spec: AnimationSpec,
+        child: Box<ResolvedNode>,
+        rect: Rect,
+        state: TransitionState,
+    },
     Layout {
-        layout: Rc<dyn Layout>,
+        layout: Rc<dyn BoxLayout>,
         children: Vec<ResolvedNode>,
+        /// Removed transitions which are still fading out; these are painted, but not laid out.
+        exiting: Vec<ResolvedNode>,
+        /// First baseline, as computed by the last `layout`.
+        baseline: Option<f32>,
+        rect: Rect,
+        cache: LayoutCache,
+        /// Whether `children` were kept from the previous frame instead of being rebuilt, as this was off-screen.
+        stale: bool,
+    },
+    Paragraph {
+        text: String,
+        font: String,
+        size: f32,
+        fill: Paint,
+        style: ParagraphStyle,
+        /// Wrapped to the width of `rect` by `layout`.
+        paragraph: Rc<RefCell<sk::textlayout::Paragraph>>,
+        rect: Rect,
+    },
+    RichText {
+        spans: Vec<TextSpan>,
+        style: ParagraphStyle,
+        paragraph: Rc<RefCell<sk::textlayout::Paragraph>>,
         rect: Rect,
     },
     Text {
@@ -413,6 +1755,25 @@ pub enum ResolvedNode {
         background: Option<Paint>,
         border: f32,
         border_fill: Option<Paint>,
+        shadows: Vec<Shadow>,
+    },
+    Path {
+        path: Path,
+        /// `path` offset to the origin.
+        sk_path: sk::Path,
+        /// Area covered by `stroke`, offset along with `sk_path`.
+        outline: Option<sk::Path>,
+        fill: Option<Paint>,
+        stroke: Option<Stroke>,
+        rect: Rect,
+    },
+    Image {
+        image: Rc<Image>,
+        sk_image: sk::Image,
+        fit: ImageFit,
+        align: Point2,
+        quality: sk::FilterQuality,
+        rect: Rect,
     },
     Draw {
         rect: Rect,
@@ -420,35 +1781,245 @@ pub enum ResolvedNode {
     },
 }
 
-impl ResolvedNode {
-    /// Performs top-down layout of this resolved node tree.
-    ///
-    /// Layout is applied in-place (hence the `&mut self`).
-    pub fn perform_layout(&mut self) {
+impl ResolvedNode {
+    /// Performs layout of this resolved node tree within `constraints`, placing it at the origin.
+    ///
+    /// Layout is applied in-place (hence the `&mut self`).
+    pub fn perform_layout(&mut self, constraints: BoxConstraints) {
+        self.clear_layout_caches();
+        self.layout(constraints);
+        self.set_position(Point2::zero());
+        self.offset_children();
+    }
+
+    /// Sizes this node (and lays out its children) within `constraints`, returning the chosen size.
+    ///
+    /// Until `perform_layout` finishes, the position of every node is relative to its parent; see `set_position`.
+    /// `Text` nodes always take their measured size.
+    pub fn layout(&mut self, constraints: BoxConstraints) -> Size2 {
+        let size = match self {
+            ResolvedNode::Interact { child, .. }
+            | ResolvedNode::Capture { child, .. }
+            | ResolvedNode::Keyed { child, .. }
+            | ResolvedNode::Layer { child, .. }
+            | ResolvedNode::Clip { child, .. }
+            | ResolvedNode::Transform { child, .. }
+            | ResolvedNode::Effect { child, .. }
+            | ResolvedNode::Transition { child, .. } => {
+                let size = child.layout(constraints);
+                child.set_position(Point2::zero());
+                size
+            }
+            ResolvedNode::Layout {
+                layout,
+                children,
+                baseline,
+                cache,
+                ..
+            } => {
+                if let Some(size) = cache.restore(constraints, children, baseline) {
+                    size
+                } else {
+                    let size = layout.layout(constraints, children);
+                    *baseline = layout.baseline(children);
+                    cache.store(constraints, size, *baseline, children);
+                    size
+                }
+            }
+            ResolvedNode::Text { rect, .. } => rect.size,
+            ResolvedNode::Paragraph { paragraph, .. }
+            | ResolvedNode::RichText { paragraph, .. } => {
+                let mut paragraph = paragraph.borrow_mut();
+                // shrink to the longest line when it's narrower than the available width, as long as that's allowed
+                let width = paragraph
+                    .max_intrinsic_width()
+                    .ceil()
+                    .max(constraints.min.width)
+                    .min(constraints.max.width);
+                paragraph.layout(width);
+                constraints.constrain(size2(width, paragraph.height()))
+            }
+            _ => constraints.constrain(self.size()),
+        };
+
+        self.set_rect(Rect::new(self.position(), size));
+        size
+    }
+
+    /// Sets the position of this node, relative to its parent while layout is in progress.
+    pub fn set_position(&mut self, position: Point2) {
+        self.set_rect(Rect::new(position, self.size()));
+    }
+
+    /// Turns the relative positions of the descendants of this node into absolute ones.
+    fn offset_children(&mut self) {
+        let origin = self.position().to_vector();
+        let children = match self {
+            ResolvedNode::Layout {
+                children, exiting, ..
+            } => {
+                // exiting children are no longer laid out, so their whole subtree is relative to this layout (see `diff_resolve`)
+                for child in exiting {
+                    child.translate(origin.to_point());
+                }
+                children.iter_mut().collect()
+            }
+            _ => self.children_mut(),
+        };
+
+        for child in children {
+            child.set_position(child.position() + origin);
+            child.offset_children();
+        }
+    }
+
+    /// Forgets the layouts cached in this node and every descendant, which only hold within a single layout pass.
+    fn clear_layout_caches(&mut self) {
+        if let ResolvedNode::Layout { cache, .. } = self {
+            cache.entries.clear();
+        }
+        for child in self.children_mut() {
+            child.clear_layout_caches();
+        }
+    }
+
+    /// Returns `true` if a stale layout (see `ResolvedNode::Layout`) has been laid out within `cull`, meaning that it needs to be rebuilt.
+    pub(crate) fn reveals_stale(&self, cull: &Rect) -> bool {
+        let cull = match self.local_transform().map(|transform| transform.inverse()) {
+            Some(Some(inverse)) => inverse.outer_transformed_rect(cull),
+            Some(None) => return false,
+            None => *cull,
+        };
+
+        match self {
+            ResolvedNode::Layout {
+                stale: true, rect, ..
+            } => cull.intersects(rect),
+            _ => self
+                .children()
+                .into_iter()
+                .any(|child| child.reveals_stale(&cull)),
+        }
+    }
+
+    /// Appends the rectangle (and baseline) of this node and every descendant, in pre-order.
+    fn save_geometry(&self, geometry: &mut Vec<(Rect, Option<f32>)>) {
+        let baseline = match self {
+            ResolvedNode::Layout { baseline, .. } => *baseline,
+            _ => None,
+        };
+        geometry.push((self.rect(), baseline));
+        for child in self.children() {
+            child.save_geometry(geometry);
+        }
+    }
+
+    /// Reverses `save_geometry`, relaying out paragraphs which have since been laid out at a different width.
+    fn restore_geometry(&mut self, geometry: &mut impl Iterator<Item = (Rect, Option<f32>)>) {
+        if let Some((rect, saved_baseline)) = geometry.next() {
+            self.set_rect(rect);
+            match self {
+                ResolvedNode::Layout { baseline, .. } => *baseline = saved_baseline,
+                ResolvedNode::Paragraph { paragraph, .. }
+                | ResolvedNode::RichText { paragraph, .. } => {
+                    let mut paragraph = paragraph.borrow_mut();
+                    if paragraph.max_width() != rect.size.width {
+                        paragraph.layout(rect.size.width);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for child in self.children_mut() {
+            child.restore_geometry(geometry);
+        }
+    }
+
+    /// Moves this node along with all of its descendants by `offset`.
+    pub(crate) fn translate(&mut self, offset: Point2) {
+        self.set_position(self.position() + offset.to_vector());
+        for child in self.children_mut() {
+            child.translate(offset);
+        }
+    }
+
+    /// Returns the distance from the top of this node to its first line of text, if it has one.
+    ///
+    /// Baselines of layouts are only known after they have been laid out.
+    pub fn baseline(&self) -> Option<f32> {
+        match self {
+            ResolvedNode::Interact { child, .. }
+            | ResolvedNode::Capture { child, .. }
+            | ResolvedNode::Keyed { child, .. }
+            | ResolvedNode::Layer { child, .. }
+            | ResolvedNode::Clip { child, .. }
+            | ResolvedNode::Transform { child, .. }
+            | ResolvedNode::Effect { child, .. }
+            | ResolvedNode::Transition { child, .. } => child.baseline(),
+            ResolvedNode::Layout { baseline, .. } => *baseline,
+            // text is shaped with its baseline at the ascent of the primary font (which skia measures upwards, hence negative)
+            ResolvedNode::Text { sk_font, blob, .. } => {
+                blob.as_ref().map(|_| -sk_font.metrics().1.ascent)
+            }
+            ResolvedNode::Paragraph { paragraph, .. }
+            | ResolvedNode::RichText { paragraph, .. } => {
+                Some(paragraph.borrow().alphabetic_baseline())
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the span of a `RichText` node under `pos`, if any.
+    pub fn span_at(&self, pos: Point2) -> Option<&TextSpan> {
+        match self {
+            ResolvedNode::RichText { spans, .. } => Some(&spans[self.span_index_at(pos)?]),
+            _ => None,
+        }
+    }
+
+    /// Returns the index of the span of a `RichText` node under `pos`, if any.
+    pub fn span_index_at(&self, pos: Point2) -> Option<usize> {
+        let (spans, paragraph, rect) = match self {
+            ResolvedNode::RichText {
+                spans,
+                paragraph,
+                rect,
+                ..
+            } => (spans, paragraph.borrow(), rect),
+            _ => return None,
+        };
+
+        range_index_at(
+            &paragraph,
+            &span_ranges(spans),
+            pos - rect.origin.to_vector(),
+        )
+    }
+
+    /// Returns `true` if this is a passthrough `Interact` node, and nothing within it handles input at `point` (see `interact`).
+    pub(crate) fn passes_through(&self, point: Point2) -> bool {
         match self {
-            ResolvedNode::Interact { child, rect, .. } => {
-                child.set_rect(*rect);
-                child.perform_layout();
-            }
-            ResolvedNode::Layout {
-                layout,
-                children,
-                rect,
+            ResolvedNode::Interact {
+                passthrough: true,
+                child,
                 ..
-            } => {
-                let rects = layout.position(
-                    *rect,
-                    &children
-                        .iter()
-                        .map(|child| child.size())
-                        .collect::<Vec<_>>(),
-                );
-                for (rect, child) in rects.into_iter().zip(children.iter_mut()) {
-                    child.set_rect(rect);
-                    child.perform_layout();
-                }
-            }
-            _ => {}
+            } => !child.handles_input_at(point),
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if this node itself responds to input at `point`, e.g. a clickable span of rich text.
+    fn handles_input_at(&self, point: Point2) -> bool {
+        match self {
+            ResolvedNode::RichText { .. } => self
+                .span_at(point)
+                .map_or(false, |span| span.on_click.is_some()),
+            ResolvedNode::Capture { child, .. }
+            | ResolvedNode::Keyed { child, .. }
+            | ResolvedNode::Layer { child, .. }
+            | ResolvedNode::Effect { child, .. } => child.handles_input_at(point),
+            _ => false,
         }
     }
 
@@ -457,9 +2028,19 @@ impl ResolvedNode {
         match self {
             ResolvedNode::Interact { rect, .. }
             | ResolvedNode::Capture { rect, .. }
+            | ResolvedNode::Keyed { rect, .. }
+            | ResolvedNode::Layer { rect, .. }
+            | ResolvedNode::Clip { rect, .. }
+            | ResolvedNode::Transform { rect, .. }
+            | ResolvedNode::Effect { rect, .. }
+            | ResolvedNode::Transition { rect, .. }
             | ResolvedNode::Layout { rect, .. }
             | ResolvedNode::Rectangle { rect, .. }
+            | ResolvedNode::Image { rect, .. }
+            | ResolvedNode::Path { rect, .. }
             | ResolvedNode::Text { rect, .. }
+            | ResolvedNode::Paragraph { rect, .. }
+            | ResolvedNode::RichText { rect, .. }
             | ResolvedNode::Draw { rect, .. } => rect.origin,
             _ => crate::point2(0., 0.),
         }
@@ -470,9 +2051,19 @@ impl ResolvedNode {
         match self {
             ResolvedNode::Interact { rect, .. }
             | ResolvedNode::Capture { rect, .. }
+            | ResolvedNode::Keyed { rect, .. }
+            | ResolvedNode::Layer { rect, .. }
+            | ResolvedNode::Clip { rect, .. }
+            | ResolvedNode::Transform { rect, .. }
+            | ResolvedNode::Effect { rect, .. }
+            | ResolvedNode::Transition { rect, .. }
             | ResolvedNode::Layout { rect, .. }
             | ResolvedNode::Rectangle { rect, .. }
+            | ResolvedNode::Image { rect, .. }
+            | ResolvedNode::Path { rect, .. }
             | ResolvedNode::Text { rect, .. }
+            | ResolvedNode::Paragraph { rect, .. }
+            | ResolvedNode::RichText { rect, .. }
             | ResolvedNode::Draw { rect, .. } => rect.size,
             _ => size2(0., 0.),
         }
@@ -490,8 +2081,18 @@ impl ResolvedNode {
         match self {
             ResolvedNode::Interact { rect, .. }
             | ResolvedNode::Capture { rect, .. }
+            | ResolvedNode::Keyed { rect, .. }
+            | ResolvedNode::Layer { rect, .. }
+            | ResolvedNode::Clip { rect, .. }
+            | ResolvedNode::Transform { rect, .. }
+            | ResolvedNode::Effect { rect, .. }
+            | ResolvedNode::Transition { rect, .. }
             | ResolvedNode::Layout { rect, .. }
             | ResolvedNode::Rectangle { rect, .. }
+            | ResolvedNode::Image { rect, .. }
+            | ResolvedNode::Path { rect, .. }
+            | ResolvedNode::Paragraph { rect, .. }
+            | ResolvedNode::RichText { rect, .. }
             | ResolvedNode::Draw { rect, .. } => *rect = r,
             ResolvedNode::Text { rect, .. } => rect.origin = r.origin,
             _ => {}
@@ -500,24 +2101,188 @@ impl ResolvedNode {
 
     pub fn children(&self) -> Vec<&ResolvedNode> {
         match self {
-            ResolvedNode::Interact { child, .. } | ResolvedNode::Capture { child, .. } => {
-                vec![child.as_ref()]
-            }
-            ResolvedNode::Layout { children, .. } => children.iter().collect(),
+            ResolvedNode::Interact { child, .. }
+            | ResolvedNode::Capture { child, .. }
+            | ResolvedNode::Keyed { child, .. }
+            | ResolvedNode::Layer { child, .. }
+            | ResolvedNode::Clip { child, .. }
+            | ResolvedNode::Transform { child, .. }
+            | ResolvedNode::Effect { child, .. }
+            | ResolvedNode::Transition { child, .. } => vec![child.as_ref()],
+            ResolvedNode::Layout {
+                children, exiting, ..
+            } => exiting.iter().chain(children.iter()).collect(),
             _ => vec![],
         }
     }
 
     pub fn children_mut(&mut self) -> Vec<&mut ResolvedNode> {
         match self {
-            ResolvedNode::Interact { child, .. } | ResolvedNode::Capture { child, .. } => {
-                vec![child.as_mut()]
-            }
-            ResolvedNode::Layout { children, .. } => children.iter_mut().collect(),
+            ResolvedNode::Interact { child, .. }
+            | ResolvedNode::Capture { child, .. }
+            | ResolvedNode::Keyed { child, .. }
+            | ResolvedNode::Layer { child, .. }
+            | ResolvedNode::Clip { child, .. }
+            | ResolvedNode::Transform { child, .. }
+            | ResolvedNode::Effect { child, .. }
+            | ResolvedNode::Transition { child, .. } => vec![child.as_mut()],
+            ResolvedNode::Layout {
+                children, exiting, ..
+            } => exiting.iter_mut().chain(children.iter_mut()).collect(),
             _ => vec![],
         }
     }
 
+    /// Returns the key of this node if it is a `Keyed` node.
+    pub fn key(&self) -> Option<Key> {
+        if let ResolvedNode::Keyed { key, .. } = self {
+            Some(*key)
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if `point` lands on this node, taking the rounded corners of `Clip` nodes and the shape of `Path` nodes into account.
+    pub fn hit_test(&self, point: Point2) -> bool {
+        let rect = self.rect();
+        if !rect.contains(point) {
+            return false;
+        }
+
+        match self {
+            ResolvedNode::Clip { corner_radii, .. } => {
+                // upper-left, upper-right, lower-right, lower-left
+                let corners = [
+                    (rect.min(), 1., 1.),
+                    (point2(rect.max_x(), rect.min_y()), -1., 1.),
+                    (rect.max(), -1., -1.),
+                    (point2(rect.min_x(), rect.max_y()), 1., -1.),
+                ];
+
+                for ((corner, sx, sy), radius) in corners.iter().zip(corner_radii) {
+                    let center: Point2 = point2(corner.x + sx * radius, corner.y + sy * radius);
+                    let (dx, dy) = ((point.x - center.x) * sx, (point.y - center.y) * sy);
+                    if dx < 0. && dy < 0. && dx * dx + dy * dy > radius * radius {
+                        return false;
+                    }
+                }
+
+                true
+            }
+            ResolvedNode::Path {
+                sk_path,
+                outline,
+                fill,
+                ..
+            } => {
+                let local = sk::Point::new(point.x - rect.origin.x, point.y - rect.origin.y);
+                (fill.is_some() && sk_path.contains(local))
+                    || outline
+                        .as_ref()
+                        .map_or(false, |outline| outline.contains(local))
+            }
+            // wrappers share the rect of their child, so defer to its shape
+            ResolvedNode::Interact { child, .. }
+            | ResolvedNode::Capture { child, .. }
+            | ResolvedNode::Keyed { child, .. }
+            | ResolvedNode::Layer { child, .. }
+            | ResolvedNode::Effect { child, .. } => child.hit_test(point),
+            ResolvedNode::Transition { child, state, .. } => {
+                !state.exiting && child.hit_test(point)
+            }
+            _ => true,
+        }
+    }
+
+    /// Returns the z-order of this node; anything other than a `Layer` node is `ZOrder::Above`.
+    pub fn z_order(&self) -> ZOrder {
+        if let ResolvedNode::Layer { z_order, .. } = self {
+            *z_order
+        } else {
+            ZOrder::Above
+        }
+    }
+
+    /// Returns `true` if this node is drawn in a layer of its own (i.e. `ZOrder::Top` or `ZOrder::Bottom`), rather than with its parent.
+    pub fn is_detached_layer(&self) -> bool {
+        matches!(self.z_order(), ZOrder::Top | ZOrder::Bottom)
+    }
+
+    /// Returns the children in the order they are painted, excluding detached layers.
+    ///
+    /// Children which are `ZOrder::Below` come first, and should be painted before this node itself.
+    pub fn paint_children(&self) -> Vec<&ResolvedNode> {
+        let mut children = self
+            .children()
+            .into_iter()
+            .filter(|child| !child.is_detached_layer())
+            .collect::<Vec<_>>();
+        // stable, so painter's order is kept within each group
+        children.sort_by_key(|child| child.z_order() != ZOrder::Below);
+        children
+    }
+
+    /// Returns the transform from the space of this node's children to the space of this node's parent, if this is a `Transform` node or a moving `Transition` node.
+    pub fn local_transform(&self) -> Option<Transform2> {
+        match self {
+            ResolvedNode::Transform {
+                transform,
+                origin,
+                rect,
+                ..
+            } => Some(transform_about(transform, *origin, rect)),
+            ResolvedNode::Transition { rect, state, .. } if state.rect() != *rect => {
+                let to = state.rect();
+                let scale = |to: f32, from: f32| if from > 0. { to / from } else { 1. };
+                Some(
+                    Transform2::translation(-rect.origin.x, -rect.origin.y)
+                        .then_scale(
+                            scale(to.size.width, rect.size.width),
+                            scale(to.size.height, rect.size.height),
+                        )
+                        .then_translate(to.origin.to_vector()),
+                )
+            }
+            _ => None,
+        }
+    }
+
+    /// Builds the list of layers within this tree, ordered from bottom-most to top-most.
+    ///
+    /// Each layer is the root of a subtree which should be painted with its detached layers skipped (see `paint_children`), as they are listed separately.
+    /// Alongside each layer is the transform of its ancestors, mapping from the layer's space to the space of this node.
+    /// Hit testing should walk this list in reverse.
+    pub fn layers(&self) -> Vec<(&ResolvedNode, Transform2)> {
+        let mut bottom = Vec::new();
+        let mut top = Vec::new();
+        self.collect_layers(Transform2::identity(), &mut bottom, &mut top);
+
+        bottom.push((self, Transform2::identity()));
+        bottom.append(&mut top);
+        bottom
+    }
+
+    fn collect_layers<'a>(
+        &'a self,
+        transform: Transform2,
+        bottom: &mut Vec<(&'a ResolvedNode, Transform2)>,
+        top: &mut Vec<(&'a ResolvedNode, Transform2)>,
+    ) {
+        let transform = self
+            .local_transform()
+            .map(|local| local.then(&transform))
+            .unwrap_or(transform);
+
+        for child in self.children() {
+            match child.z_order() {
+                ZOrder::Bottom => bottom.push((child, transform)),
+                ZOrder::Top => top.push((child, transform)),
+                _ => {}
+            }
+            child.collect_layers(transform, bottom, top);
+        }
+    }
+
     pub fn is_interact(&self) -> bool {
         matches!(self, ResolvedNode::Interact { .. })
     }
@@ -533,6 +2298,73 @@ impl ResolvedNode {
         }
     }
 
+    /// Advances the animations of `Transition` nodes to the current frame, and drops exiting transitions which have faded out.
+    ///
+    /// This should be invoked after layout, so that transitions head towards their new rectangle.
+    pub fn advance_transitions(&mut self, cx: &mut Cx) {
+        self.advance_transitions_within(cx, Point2::zero());
+    }
+
+    /// Same as `advance_transitions`, where `origin` is the position of the layout this node was positioned by.
+    fn advance_transitions_within(&mut self, cx: &mut Cx, origin: Point2) {
+        if let ResolvedNode::Transition {
+            spec, rect, state, ..
+        } = self
+        {
+            if state.advance(*rect, origin, spec, cx.frame()) {
+                cx.request_redraw();
+            }
+        }
+
+        let origin = match self {
+            ResolvedNode::Layout { rect, .. } => rect.origin,
+            _ => origin,
+        };
+        for child in self.children_mut() {
+            child.advance_transitions_within(cx, origin);
+        }
+
+        if let ResolvedNode::Layout { exiting, .. } = self {
+            // `retain` only hands out shared references, and `transition_state_mut` needs a mutable one
+            let mut i = 0;
+            while i < exiting.len() {
+                if exiting[i]
+                    .transition_state_mut()
+                    .map_or(false, |state| state.opacity.is_running())
+                {
+                    i += 1;
+                } else {
+                    exiting.remove(i);
+                }
+            }
+        }
+    }
+
+    /// Marks this transition (or keyed transition) as removed, so that it fades out. Returns `false` if this isn't a transition.
+    pub(crate) fn exit(&mut self) -> bool {
+        if let Some(state) = self.transition_state_mut() {
+            state.exiting = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Reverses `exit`, for a removed transition whose key has returned while it was fading out.
+    pub(crate) fn enter(&mut self) {
+        if let Some(state) = self.transition_state_mut() {
+            state.exiting = false;
+        }
+    }
+
+    fn transition_state_mut(&mut self) -> Option<&mut TransitionState> {
+        match self {
+            ResolvedNode::Transition { state, .. } => Some(state),
+            ResolvedNode::Keyed { child, .. } => child.transition_state_mut(),
+            _ => None,
+        }
+    }
+
     pub fn is_null(&self) -> bool {
         matches!(self, ResolvedNode::Null)
     }
@@ -577,10 +2409,60 @@ impl ResolvedNode {
                 callback: callback.clone(),
                 rect: *rect,
             },
-            ResolvedNode::Layout { layout, rect, .. } => ResolvedNode::Layout {
+            ResolvedNode::Keyed { key, rect, .. } => ResolvedNode::Keyed {
+                key: *key,
+                child: Box::new(ResolvedNode::Null),
+                rect: *rect,
+            },
+            ResolvedNode::Layer { z_order, rect, .. } => ResolvedNode::Layer {
+                z_order: *z_order,
+                child: Box::new(ResolvedNode::Null),
+                rect: *rect,
+            },
+            ResolvedNode::Clip {
+                corner_radii, rect, ..
+            } => ResolvedNode::Clip {
+                corner_radii: *corner_radii,
+                child: Box::new(ResolvedNode::Null),
+                rect: *rect,
+            },
+            ResolvedNode::Transform {
+                transform,
+                origin,
+                rect,
+                ..
+            } => ResolvedNode::Transform {
+                transform: *transform,
+                origin: *origin,
+                child: Box::new(ResolvedNode::Null),
+                rect: *rect,
+            },
+            ResolvedNode::Effect { effect, rect, .. } => ResolvedNode::Effect {
+                effect: effect.clone(),
+                child: Box::new(ResolvedNode::Null),
+                rect: *rect,
+            },
+            ResolvedNode::Transition {
+                spec, rect, state, ..
+            } => ResolvedNode::Transition {
+                spec: *spec,
+                child: Box::new(ResolvedNode::Null),
+                rect: *rect,
+                state: state.clone(),
+            },
+            ResolvedNode::Layout {
+                layout,
+                baseline,
+                rect,
+                ..
+            } => ResolvedNode::Layout {
                 layout: Rc::clone(layout),
                 children: Vec::new(),
+                exiting: Vec::new(),
+                baseline: *baseline,
                 rect: *rect,
+                cache: Default::default(),
+                stale: false,
             },
             _ => self.clone(),
         }
@@ -607,13 +2489,97 @@ impl Font {
     }
 }
 
+/// Collects the runs of each fallback font into a single text blob, one after the other along the same baseline.
+struct FallbackRunHandler {
+    builder: sk::TextBlobBuilder,
+    pen: sk::Point,
+    glyphs: Vec<sk::GlyphId>,
+    positions: Vec<sk::Point>,
+}
+
+impl FallbackRunHandler {
+    fn new(pen: sk::Point) -> Self {
+        FallbackRunHandler {
+            builder: sk::TextBlobBuilder::new(),
+            pen,
+            glyphs: Vec::new(),
+            positions: Vec::new(),
+        }
+    }
+}
+
+impl sk::shaper::RunHandler for FallbackRunHandler {
+    fn begin_line(&mut self) {}
+
+    fn run_info(&mut self, _info: &sk::shaper::run_handler::RunInfo) {}
+
+    fn commit_run_info(&mut self) {}
+
+    fn run_buffer(
+        &mut self,
+        info: &sk::shaper::run_handler::RunInfo,
+    ) -> sk::shaper::run_handler::Buffer<'_> {
+        self.glyphs.resize(info.glyph_count, 0);
+        self.positions
+            .resize(info.glyph_count, sk::Point::default());
+        sk::shaper::run_handler::Buffer::new(&mut self.glyphs, &mut self.positions, self.pen)
+    }
+
+    fn commit_run_buffer(&mut self, info: &sk::shaper::run_handler::RunInfo) {
+        let (glyphs, positions) = self
+            .builder
+            .alloc_run_pos(info.font, info.glyph_count, None);
+        glyphs.copy_from_slice(&self.glyphs);
+        positions.copy_from_slice(&self.positions);
+        self.pen.x += info.advance.x;
+    }
+
+    fn commit_line(&mut self) {}
+}
+
 /// Stores resources that will be used throughout the UI (e.g. fonts).
 pub struct Resources {
     pub fonts: FxHashMap<String, Rc<Font>>,
+    /// Fonts to try, in order, for characters which the font of the same name doesn't have.
+    ///
+    /// The fallbacks of `default_font` apply to every font; see `Resources::font_chain`.
+    pub font_fallbacks: FxHashMap<String, Vec<String>>,
+    /// Font used in place of fonts which aren't in `fonts`.
+    pub default_font: String,
+    /// System fonts picked for characters which no font chain covers, or `None` if no system font has them either.
+    pub fallback_cache: FxHashMap<char, Option<String>>,
+    /// Typefaces of the system fonts in `fallback_cache` which aren't in `fonts`, by their PostScript name.
+    pub system_fonts: FxHashMap<String, sk::Typeface>,
     pub fallback_text_size: f32,
     pub fallback_text_fill: Paint,
     pub shaper_cache: FxHashMap<(String, String, OrderedFloat<f32>), (sk::TextBlob, Size2)>,
     pub font_cache: FxHashMap<(String, OrderedFloat<f32>), Rc<sk::Font>>,
+    /// Skia images keyed by the address of the `Rc<Image>` they were created from.
+    pub image_cache: FxHashMap<*const Image, (Weak<Image>, sk::Image)>,
+    /// Compiled `Paint::Shader`s keyed by their source.
+    pub shader_cache: FxHashMap<String, sk::RuntimeEffect>,
+    /// `fonts` as seen by paragraphs; rebuilt after a font is added (see `Resources::font_collection`).
+    pub font_collection: Option<sk::textlayout::FontCollection>,
+}
+
+impl Default for Resources {
+    /// Empty resources which fall back to the `"sans-serif"` font, in white at 12 logical pixels.
+    fn default() -> Self {
+        Resources {
+            fonts: Default::default(),
+            font_fallbacks: Default::default(),
+            default_font: String::from("sans-serif"),
+            fallback_cache: Default::default(),
+            system_fonts: Default::default(),
+            fallback_text_size: 12.,
+            fallback_text_fill: Paint::Solid(Color::new(1., 1., 1., 1.)),
+            shaper_cache: Default::default(),
+            font_cache: Default::default(),
+            image_cache: Default::default(),
+            shader_cache: Default::default(),
+            font_collection: None,
+        }
+    }
 }
 
 impl Resources {
@@ -623,7 +2589,7 @@ impl Resources {
         name: impl Into<String>,
         font: font_kit::font::Font,
     ) -> Result<(), Error> {
-        self.fonts.insert(name.into(), Rc::new(Font::new(font)?));
+        self.insert_font(name.into(), Font::new(font)?);
         Ok(())
     }
 
@@ -638,9 +2604,9 @@ impl Resources {
         families: &[String],
         properties: &FontProperties,
     ) -> Result<(), Error> {
-        self.fonts.insert(
+        self.insert_font(
             name.into(),
-            Rc::new(Font::new(font_kit::font::Font::from_handle(
+            Font::new(font_kit::font::Font::from_handle(
                 &font_kit::source::SystemSource::new().select_best_match(
                     &families
                         .iter()
@@ -648,7 +2614,7 @@ impl Resources {
                         .collect::<Vec<_>>(),
                     properties,
                 )?,
-            )?)?),
+            )?)?,
         );
         Ok(())
     }
@@ -663,13 +2629,23 @@ impl Resources {
         index: impl Into<Option<u32>>,
     ) -> Result<(), Error> {
         let index = index.into().unwrap_or(0);
-        self.fonts.insert(
+        self.insert_font(
             name.into(),
-            Rc::new(Font::new(font_kit::font::Font::from_bytes(bytes, index)?)?),
+            Font::new(font_kit::font::Font::from_bytes(bytes, index)?)?,
         );
         Ok(())
     }
 
+    fn insert_font(&mut self, name: String, font: Font) {
+        self.fonts.insert(name, Rc::new(font));
+        // text shaped before may have used another font in its place (see `font_chain`), and the font may have replaced one of the same name
+        self.shaper_cache.clear();
+        self.font_cache.clear();
+        // characters which fell back to system fonts (or to nothing) may be covered by the new font
+        self.fallback_cache.clear();
+        self.font_collection = None;
+    }
+
     /// Returns a reference to the font stored at `name`, if any.
     pub fn font(&self, name: &str) -> Option<&Font> {
         self.fonts.get(name).map(|x| x.as_ref())
@@ -678,4 +2654,457 @@ impl Resources {
     pub fn has_font(&self, name: &str) -> bool {
         self.fonts.contains_key(name)
     }
+
+    /// Sets the fonts to fall back to, in order, for characters which the font at `name` doesn't have.
+    pub fn set_font_fallbacks(&mut self, name: impl Into<String>, fallbacks: &[String]) {
+        self.font_fallbacks.insert(name.into(), fallbacks.to_vec());
+        // text shaped before may have fallen back differently
+        self.shaper_cache.clear();
+    }
+
+    /// Returns the family names which paragraphs try for `name`: `name` itself, its fallbacks, the fallbacks of `default_font` and finally `default_font`.
+    pub fn font_families(&self, name: &str) -> Vec<String> {
+        let mut families = vec![name.to_owned()];
+        for family in self
+            .font_fallbacks
+            .get(name)
+            .into_iter()
+            .chain(self.font_fallbacks.get(&self.default_font))
+            .flatten()
+            .chain(std::iter::once(&self.default_font))
+        {
+            if !families.contains(family) {
+                families.push(family.clone());
+            }
+        }
+        families
+    }
+
+    /// Returns the font which text in `name` is primarily shaped with (see `font_chain`).
+    pub(crate) fn primary_font(&self, name: &str) -> Option<&Rc<Font>> {
+        self.fonts
+            .get(name)
+            .or_else(|| self.fonts.get(&self.default_font))
+    }
+
+    /// Returns the names of the fonts to shape text in `name` with, in order of preference.
+    ///
+    /// The first font is `name` itself, or `default_font` if there is no such font; the rest are those of `font_families` which are in `fonts`.
+    /// Fails with `Error::InvalidFont` if neither `name` nor `default_font` are in `fonts`.
+    pub fn font_chain(&self, name: &str) -> Result<Vec<String>, Error> {
+        let primary = if self.has_font(name) {
+            name
+        } else if self.has_font(&self.default_font) {
+            &self.default_font
+        } else {
+            return Err(Error::InvalidFont(name.to_owned()));
+        };
+
+        let mut chain = vec![primary.to_owned()];
+        for family in self.font_families(name) {
+            if self.has_font(&family) && !chain.contains(&family) {
+                chain.push(family);
+            }
+        }
+        Ok(chain)
+    }
+
+    /// Returns the name of a system font which has a glyph for `c`, as found in `fonts` or `system_fonts`.
+    ///
+    /// Lookups are cached in `fallback_cache`.
+    pub fn system_fallback(&mut self, c: char) -> Option<String> {
+        if let Some(name) = self.fallback_cache.get(&c) {
+            return name.clone();
+        }
+
+        // fonts picked for earlier characters are likely to cover the rest of their script too
+        let mut found = self
+            .fallback_cache
+            .values()
+            .flatten()
+            .find(|name| self.has_glyph(name, c))
+            .cloned();
+
+        if found.is_none() {
+            if let Some(typeface) = sk::FontMgr::default().match_family_style_character(
+                "",
+                sk::FontStyle::default(),
+                &[],
+                c as i32,
+            ) {
+                let name = typeface
+                    .post_script_name()
+                    .unwrap_or_else(|| typeface.family_name());
+                // a font of the same name in `fonts` is used in its place (see `has_glyph`)
+                let covered = match self.fonts.get(&name) {
+                    Some(font) => font.font.glyph_for_char(c).is_some(),
+                    None => typeface.unichar_to_glyph(c as i32) != 0,
+                };
+                if covered {
+                    if !self.has_font(&name) {
+                        self.system_fonts.entry(name.clone()).or_insert(typeface);
+                    }
+                    found = Some(name);
+                }
+            }
+        }
+
+        self.fallback_cache.insert(c, found.clone());
+        found
+    }
+
+    /// Returns whether the font at `name`, in `fonts` or `system_fonts`, has a glyph for `c`.
+    fn has_glyph(&self, name: &str, c: char) -> bool {
+        match self.fonts.get(name) {
+            Some(font) => font.font.glyph_for_char(c).is_some(),
+            None => self
+                .system_fonts
+                .get(name)
+                .map_or(false, |typeface| typeface.unichar_to_glyph(c as i32) != 0),
+        }
+    }
+
+    /// Splits `text` into byte ranges which are each shaped with a single font of `chain`, or a system fallback.
+    fn fallback_runs(&mut self, text: &str, chain: &[String]) -> Vec<(Range<usize>, String)> {
+        let mut runs: Vec<(Range<usize>, String)> = Vec::new();
+        for (i, c) in text.char_indices() {
+            let end = i + c.len_utf8();
+
+            // spaces, joiners and variation selectors belong with the characters around them
+            let attached = c.is_whitespace()
+                || c.is_control()
+                || matches!(c, '\u{200c}'..='\u{200d}' | '\u{fe00}'..='\u{fe0f}');
+            if let Some((range, _)) = runs.last_mut().filter(|_| attached) {
+                range.end = end;
+                continue;
+            }
+
+            let font = chain
+                .iter()
+                .find(|name| self.fonts[*name].font.glyph_for_char(c).is_some())
+                .cloned()
+                .or_else(|| self.system_fallback(c))
+                .unwrap_or_else(|| chain[0].clone());
+
+            match runs.last_mut() {
+                Some((range, last)) if *last == font => range.end = end,
+                _ => runs.push((i..end, font)),
+            }
+        }
+        runs
+    }
+
+    /// Returns the Skia font for the font at `name`, which must be in `fonts` or `system_fonts`.
+    fn sk_font(&mut self, name: &str, size: f32) -> Rc<sk::Font> {
+        let typeface = match self.fonts.get(name) {
+            Some(font) => &font.sk,
+            None => &self.system_fonts[name],
+        };
+        self.font_cache
+            .entry((name.to_owned(), OrderedFloat(size)))
+            .or_insert_with(|| {
+                let mut font = sk::Font::new(typeface, size);
+                font.set_hinting(sk::FontHinting::Normal).set_subpixel(true);
+                Rc::new(font)
+            })
+            .clone()
+    }
+
+    /// Returns the collection of fonts used to lay out paragraphs, where each font goes by its name in `fonts`.
+    ///
+    /// Characters which none of `fonts` cover fall back to the system fonts.
+    /// The collection is cached; if `fonts` is modified directly, reset `font_collection` to `None`.
+    pub fn font_collection(&mut self) -> sk::textlayout::FontCollection {
+        let fonts = &self.fonts;
+        self.font_collection
+            .get_or_insert_with(|| {
+                let mut provider = sk::textlayout::TypefaceFontProvider::new();
+                for (name, font) in fonts {
+                    provider.register_typeface(font.sk.clone(), Some(name));
+                    // also under its own family name, grouping the weights and slants of a family
+                    provider.register_typeface(font.sk.clone(), None::<&str>);
+                }
+
+                let mut collection = sk::textlayout::FontCollection::new();
+                collection.set_asset_font_manager(Some(provider.into()));
+                collection.set_default_font_manager(sk::FontMgr::default(), None);
+                collection
+            })
+            .clone()
+    }
+
+    /// Shapes `text` into a paragraph, which must be laid out to its final width before it is painted.
+    ///
+    /// Anything other than a solid `fill` is painted white, to be used as a mask when rendering.
+    pub fn paragraph(
+        &mut self,
+        text: &str,
+        font: &str,
+        size: f32,
+        fill: &Paint,
+        style: &ParagraphStyle,
+    ) -> sk::textlayout::Paragraph {
+        let span = TextSpan::new(text).font(font).size(size).fill(fill.clone());
+        self.rich_paragraph(&[span], style)
+    }
+
+    /// Shapes `spans` into a single paragraph, as with `paragraph`.
+    ///
+    /// Span backgrounds aren't part of the paragraph; they're painted separately when rendering, so that they can be any `Paint`.
+    pub fn rich_paragraph(
+        &mut self,
+        spans: &[TextSpan],
+        style: &ParagraphStyle,
+    ) -> sk::textlayout::Paragraph {
+        let text_styles = spans
+            .iter()
+            .map(|span| {
+                let mut text_style = sk::textlayout::TextStyle::new();
+                text_style
+                    .set_font_families(
+                        &self.font_families(span.font.as_deref().unwrap_or(&self.default_font)),
+                    )
+                    .set_font_size(span.size.unwrap_or(self.fallback_text_size))
+                    .set_font_style(sk::FontStyle::new(
+                        span.weight,
+                        sk::font_style::Width::NORMAL,
+                        if span.italic {
+                            sk::font_style::Slant::Italic
+                        } else {
+                            sk::font_style::Slant::Upright
+                        },
+                    ));
+                match span.fill.as_ref().unwrap_or(&self.fallback_text_fill) {
+                    Paint::Solid(color) => text_style.set_color(color.to_skia()),
+                    _ => text_style.set_color(sk::Color::WHITE),
+                };
+                text_style.decoration_mut().ty = span.decoration.to_skia();
+                if let Some(line_height) = style.line_height {
+                    text_style.set_height(line_height).set_height_override(true);
+                }
+                text_style
+            })
+            .collect::<Vec<_>>();
+
+        let mut paragraph_style = sk::textlayout::ParagraphStyle::new();
+        if let Some(text_style) = text_styles.first() {
+            paragraph_style.set_text_style(text_style);
+        }
+        paragraph_style
+            .set_text_align(style.align)
+            .set_max_lines(style.max_lines)
+            .set_ellipsis(&style.ellipsis);
+
+        let mut builder =
+            sk::textlayout::ParagraphBuilder::new(&paragraph_style, self.font_collection());
+        for (span, text_style) in spans.iter().zip(&text_styles) {
+            builder.push_style(text_style).add_text(&span.text).pop();
+        }
+
+        // intrinsic widths are only known after the first layout
+        let mut paragraph = builder.build();
+        paragraph.layout(f32::INFINITY);
+        paragraph
+    }
+
+    /// Returns the compiled runtime effect for the SkSL `source`, compiling and caching it if needed.
+    pub fn shader(&mut self, source: &str) -> Result<sk::RuntimeEffect, Error> {
+        if let Some(effect) = self.shader_cache.get(source) {
+            return Ok(effect.clone());
+        }
+
+        let effect = sk::runtime_effect::new(source).map_err(Error::ShaderCompilation)?;
+        self.shader_cache.insert(source.to_owned(), effect.clone());
+        Ok(effect)
+    }
+
+    /// Returns the Skia image for `image`, creating and caching it if needed.
+    ///
+    /// The pixels are copied once per `Rc<Image>`, and reusing the same Skia image lets the GPU backend keep its texture around as well.
+    /// Entries are evicted once their `Rc<Image>` has been dropped.
+    pub fn image(&mut self, image: &Rc<Image>) -> Result<sk::Image, Error> {
+        let key = Rc::as_ptr(image);
+        if let Some((weak, sk_image)) = self.image_cache.get(&key) {
+            // while the cached image is alive its address can't have been reused
+            if weak.strong_count() > 0 {
+                return Ok(sk_image.clone());
+            }
+        }
+
+        let sk_image = sk::Image::from_raster_data(
+            &sk::ImageInfo::new(
+                sk::ISize::new(image.width() as _, image.height() as _),
+                sk::ColorType::RGBA8888,
+                sk::AlphaType::Unpremul,
+                None,
+            ),
+            sk::Data::new_copy(image.as_raw()),
+            4 * image.width() as usize,
+        )
+        .ok_or(Error::ImageCreation)?;
+
+        self.image_cache
+            .retain(|_, (weak, _)| weak.strong_count() > 0);
+        self.image_cache
+            .insert(key, (Rc::downgrade(image), sk_image.clone()));
+
+        Ok(sk_image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_fits_keep_the_aspect_ratio() {
+        let bounds = Rect::new(point2(10., 20.), size2(100., 100.));
+        let wide = size2(200., 100.);
+        let center = point2(0.5, 0.5);
+
+        assert_eq!(ImageFit::Fill.apply(wide, bounds, center), bounds);
+        assert_eq!(
+            ImageFit::Contain.apply(wide, bounds, center),
+            Rect::new(point2(10., 45.), size2(100., 50.))
+        );
+        assert_eq!(
+            ImageFit::Cover.apply(wide, bounds, center),
+            Rect::new(point2(-40., 20.), size2(200., 100.))
+        );
+        assert_eq!(
+            ImageFit::None.apply(wide, bounds, point2(0., 0.)),
+            Rect::new(point2(10., 20.), wide)
+        );
+    }
+
+    #[test]
+    fn image_fit_scale_down_never_enlarges() {
+        let bounds = Rect::new(point2(10., 20.), size2(100., 100.));
+        let center = point2(0.5, 0.5);
+
+        assert_eq!(
+            ImageFit::ScaleDown.apply(size2(40., 20.), bounds, center),
+            Rect::new(point2(40., 60.), size2(40., 20.))
+        );
+        assert_eq!(
+            ImageFit::ScaleDown.apply(size2(200., 100.), bounds, center),
+            ImageFit::Contain.apply(size2(200., 100.), bounds, center)
+        );
+    }
+
+    #[test]
+    fn text_decoration_keeps_line_through() {
+        let decoration = TextDecoration {
+            line_through: true,
+            ..Default::default()
+        };
+        assert_eq!(decoration.to_skia().bits(), 4);
+
+        let all = TextDecoration {
+            underline: true,
+            overline: true,
+            line_through: true,
+        };
+        assert_eq!(all.to_skia().bits(), 7);
+    }
+
+    #[test]
+    fn span_ranges_count_utf16_code_units() {
+        let spans = [
+            TextSpan::new("ab"),
+            // the emoji is a surrogate pair
+            TextSpan::new("\u{e9}\u{1f600}"),
+            TextSpan::new(""),
+            TextSpan::new("c"),
+        ];
+
+        assert_eq!(span_ranges(&spans), vec![0..2, 2..5, 5..5, 5..6]);
+    }
+
+    fn noto_sans() -> Arc<Vec<u8>> {
+        Arc::new(include_bytes!("../benches/NotoSans-Regular.ttf").to_vec())
+    }
+
+    fn resources(fonts: &[&str]) -> Resources {
+        let mut resources = Resources::default();
+
+        for &name in fonts {
+            resources.load_font_data(name, noto_sans(), None).unwrap();
+        }
+        resources
+    }
+
+    #[test]
+    fn font_chain_needs_a_font() {
+        assert!(matches!(
+            resources(&[]).font_chain("serif"),
+            Err(Error::InvalidFont(name)) if name == "serif"
+        ));
+    }
+
+    #[test]
+    fn font_chain_follows_fallbacks_it_has() {
+        let mut resources = resources(&["sans-serif", "latin"]);
+        resources.set_font_fallbacks("body", &["missing".into(), "latin".into()]);
+
+        // `body` itself isn't loaded, so the default font takes its place
+        assert_eq!(
+            resources.font_chain("body").unwrap(),
+            vec!["sans-serif", "latin"]
+        );
+        assert_eq!(
+            resources.font_chain("latin").unwrap(),
+            vec!["latin", "sans-serif"]
+        );
+    }
+
+    #[test]
+    fn fallback_runs_switch_fonts_for_uncovered_characters() {
+        let mut resources = resources(&["sans-serif", "cjk"]);
+        // stand in for a system font covering the CJK characters, which the test font doesn't
+        resources
+            .fallback_cache
+            .insert('\u{6f22}', Some("cjk".into()));
+        resources
+            .fallback_cache
+            .insert('\u{5b57}', Some("cjk".into()));
+
+        let chain = [String::from("sans-serif")];
+        // spaces stay in the run before them
+        let runs = resources.fallback_runs("ab \u{6f22}\u{5b57} c", &chain);
+        assert_eq!(
+            runs,
+            vec![
+                (0..3, String::from("sans-serif")),
+                (3..10, String::from("cjk")),
+                (10..11, String::from("sans-serif")),
+            ]
+        );
+    }
+
+    #[test]
+    fn fallback_runs_keep_the_primary_font_without_a_fallback() {
+        let mut resources = resources(&["sans-serif"]);
+        resources.fallback_cache.insert('\u{6f22}', None);
+
+        let chain = [String::from("sans-serif")];
+        assert_eq!(
+            resources.fallback_runs("a\u{6f22}", &chain),
+            vec![(0..4, String::from("sans-serif"))]
+        );
+    }
+
+    #[test]
+    fn adding_a_font_clears_the_font_caches() {
+        let mut resources = resources(&["sans-serif"]);
+        resources.sk_font("sans-serif", 13.);
+        assert!(!resources.font_cache.is_empty());
+        resources.fallback_cache.insert('\u{6f22}', None);
+
+        resources
+            .load_font_data("sans-serif", noto_sans(), None)
+            .unwrap();
+        assert!(resources.font_cache.is_empty());
+        assert!(resources.fallback_cache.is_empty());
+    }
 }
@@ -5,20 +5,19 @@ use cape::{
     rgba, ui, Sides2,
 };
 
-use conifer::{dark::button, Apply, Button, Callback, Column, Container, LayoutBuilder, Row};
+use conifer::{Container, LayoutBuilder, RowHeight, VirtualList};
 
 // Straight-forward performance test: How long of a list can Cape handle?
+// Only the rows in view are built, so the length of the list barely matters.
 
 #[ui]
 fn counter(cx: &mut Cx) -> impl IntoNode {
-    let count = cx.state(|| 0);
-
     Container::new()
         .margin(Sides2::new_all_same(10.))
         .child(
-            Column::new()
-                .spacing(1.)
-                .children((0..50000).map(|i| format!("Item #{}", i))),
+            VirtualList::new(cx, 50000, |_, i| format!("Item #{}", i))
+                .row_height(RowHeight::Fixed(20.))
+                .height(500.),
         )
         .into_node()
 }
@@ -1,6 +1,6 @@
-use cape::node::{IntoNode, Layout, Node};
-use cape::{point2, size2, ui, Point2, Rect, Sides2, Size2};
-use std::rc::Rc;
+use cape::node::{BoxConstraints, BoxLayout, IntoNode, Node, ResolvedNode};
+use cape::{point2, size2, ui, Point2, Sides2, Size2};
+use std::{ops::Range, rc::Rc};
 
 struct RowLayout {
     items: Vec<RowItem>,
@@ -8,71 +8,21 @@ struct RowLayout {
     spacing: f32,
 }
 
-impl Layout for RowLayout {
-    fn size(&self, sizes: &[Size2]) -> Size2 {
-        let mut total = size2(
-            self.spacing * (sizes.len() - 1) as f32 + self.margin.horizontal(),
-            0.,
-        );
-        for (i, &size) in sizes.iter().enumerate() {
-            let item = &self.items[i];
-            if !item.fill {
-                total.width += size.width + item.margin.horizontal();
-                let height = size.height + item.margin.vertical();
-                if height > total.height {
-                    total.height = height;
-                }
-            }
-        }
-        total.height += self.margin.vertical();
-        total
-    }
-
-    fn position(&self, rect: Rect, sizes: &[Size2]) -> Vec<Rect> {
-        let rect = rect.inner_rect(self.margin);
-
-        let mut x = rect.origin.x;
-        let mut out = Vec::new();
-
-        let mut filled = self.spacing * (sizes.len() - 1) as f32;
-        let mut num_filled = 0;
-
-        for (i, &size) in sizes.iter().enumerate() {
-            let item = &self.items[i];
-            if !item.fill {
-                filled += size.width + item.margin.horizontal();
-            } else {
-                num_filled += 1;
-            }
-        }
-
-        let fill_dist = (rect.size.width - filled) / num_filled as f32;
-
-        for (i, &size) in sizes.iter().enumerate() {
-            let item = &self.items[i];
-            let mut size = size;
-
-            x += item.margin.left;
-
-            if item.fill {
-                size.width = fill_dist;
-            }
-
-            let y = match item.align {
-                Align::Begin => rect.origin.y,
-                Align::Middle => rect.origin.y + (rect.size.height / 2.) - (size.height / 2.),
-                Align::End => rect.origin.y + rect.size.height - size.height,
-                Align::Fill => {
-                    size.height = rect.size.height;
-                    rect.origin.y
-                }
-            };
-
-            out.push(Rect::new(point2(x, y), size));
-            x += size.width + item.margin.right + self.spacing;
-        }
-
-        out
+impl BoxLayout for RowLayout {
+    fn layout(&self, constraints: BoxConstraints, children: &mut [ResolvedNode]) -> Size2 {
+        let items = self
+            .items
+            .iter()
+            .map(|item| (item.align, item.margin, item.fill))
+            .collect::<Vec<_>>();
+        layout_linear(
+            false,
+            &items,
+            self.margin,
+            self.spacing,
+            constraints,
+            children,
+        )
     }
 }
 
@@ -82,69 +32,188 @@ struct ColumnLayout {
     spacing: f32,
 }
 
-impl Layout for ColumnLayout {
-    fn size(&self, sizes: &[Size2]) -> Size2 {
-        let mut total = size2(
-            0.,
-            self.spacing * (sizes.len() - 1) as f32 + self.margin.vertical(),
-        );
-        for (i, &size) in sizes.iter().enumerate() {
-            let item = &self.items[i];
-            total.height += size.height + item.margin.vertical();
-            let width = size.width + item.margin.horizontal();
-            if width > total.width {
-                total.width = width;
-            }
-        }
-        total.width += self.margin.horizontal();
-        total
+impl BoxLayout for ColumnLayout {
+    fn layout(&self, constraints: BoxConstraints, children: &mut [ResolvedNode]) -> Size2 {
+        let items = self
+            .items
+            .iter()
+            .map(|item| (item.align, item.margin, item.fill))
+            .collect::<Vec<_>>();
+        layout_linear(
+            true,
+            &items,
+            self.margin,
+            self.spacing,
+            constraints,
+            children,
+        )
     }
+}
 
-    fn position(&self, rect: Rect, sizes: &[Size2]) -> Vec<Rect> {
-        let rect = rect.inner_rect(self.margin);
+/// Splits `size` into its extent along the main axis and the cross axis.
+fn main_cross(size: Size2, vertical: bool) -> (f32, f32) {
+    if vertical {
+        (size.height, size.width)
+    } else {
+        (size.width, size.height)
+    }
+}
 
-        let mut y = rect.origin.y + self.margin.top;
-        let mut out = Vec::new();
+/// Inverse of `main_cross`.
+fn from_main_cross(main: f32, cross: f32, vertical: bool) -> Size2 {
+    if vertical {
+        size2(cross, main)
+    } else {
+        size2(main, cross)
+    }
+}
 
-        let mut filled = self.spacing * (sizes.len() - 1) as f32;
-        let mut num_filled = 0;
+/// Splits `sides` into (main start, main end, cross start, cross end).
+fn sides_main_cross(sides: Sides2, vertical: bool) -> (f32, f32, f32, f32) {
+    if vertical {
+        (sides.top, sides.bottom, sides.left, sides.right)
+    } else {
+        (sides.left, sides.right, sides.top, sides.bottom)
+    }
+}
 
-        for (i, &size) in sizes.iter().enumerate() {
-            let item = &self.items[i];
-            if !item.fill {
-                filled += size.height + item.margin.vertical();
-            } else {
-                num_filled += 1;
-            }
+/// Lays out children one after another, along the y axis if `vertical` and the x axis otherwise.
+///
+/// Children which `fill` evenly split whatever space along the main axis is left over by the others (if it is bounded).
+fn layout_linear(
+    vertical: bool,
+    items: &[(Align, Sides2, bool)],
+    margin: Sides2,
+    spacing: f32,
+    constraints: BoxConstraints,
+    children: &mut [ResolvedNode],
+) -> Size2 {
+    let inner = constraints.deflate(margin);
+    let (max_main, max_cross) = main_cross(inner.max, vertical);
+    let (min_main, min_cross) = main_cross(inner.min, vertical);
+
+    let item_margin = |sides: Sides2| sides_main_cross(sides, vertical);
+
+    let mut sizes = vec![Size2::zero(); children.len()];
+    let mut used = spacing * children.len().saturating_sub(1) as f32;
+    let mut num_filled = 0;
+
+    for (i, child) in children.iter_mut().enumerate() {
+        let (_, sides, fill) = items[i];
+        let (main_start, main_end, cross_start, cross_end) = item_margin(sides);
+        used += main_start + main_end;
+
+        if fill {
+            num_filled += 1;
+        } else {
+            let cross = (max_cross - cross_start - cross_end).max(0.);
+            sizes[i] = child.layout(BoxConstraints::loose(from_main_cross(
+                f32::INFINITY,
+                cross,
+                vertical,
+            )));
+            used += main_cross(sizes[i], vertical).0;
         }
+    }
 
-        let fill_dist = (rect.size.height - filled) / num_filled as f32;
-
-        for (i, &size) in sizes.iter().enumerate() {
-            let item = &self.items[i];
-            let mut size = size;
-
-            y += item.margin.top;
+    let fill_main = if max_main.is_finite() && num_filled > 0 {
+        ((max_main - used) / num_filled as f32).max(0.)
+    } else {
+        // nothing to split, so filling children take their natural size
+        f32::INFINITY
+    };
+
+    for (i, child) in children.iter_mut().enumerate() {
+        let (_, sides, fill) = items[i];
+        if fill {
+            let (_, _, cross_start, cross_end) = item_margin(sides);
+            let cross = (max_cross - cross_start - cross_end).max(0.);
+            let min_main = if fill_main.is_finite() { fill_main } else { 0. };
+            sizes[i] = child.layout(BoxConstraints::new(
+                from_main_cross(min_main, 0., vertical),
+                from_main_cross(fill_main, cross, vertical),
+            ));
+            used += main_cross(sizes[i], vertical).0;
+        }
+    }
 
-            if item.fill {
-                size.height = fill_dist;
+    let main = if num_filled > 0 && max_main.is_finite() {
+        max_main
+    } else {
+        used.max(min_main)
+    };
+
+    let baseline_of =
+        |child: &ResolvedNode| child.baseline().unwrap_or_else(|| child.size().height);
+
+    // extent of the baseline-aligned children above and below their shared baseline
+    let (above, below) = if vertical {
+        (0., 0.)
+    } else {
+        children
+            .iter()
+            .zip(items)
+            .filter(|(_, &(align, _, _))| align == Align::Baseline)
+            .fold((0f32, 0f32), |(above, below), (child, &(_, sides, _))| {
+                let baseline = baseline_of(child);
+                (
+                    above.max(sides.top + baseline),
+                    below.max(child.size().height - baseline + sides.bottom),
+                )
+            })
+    };
+
+    let cross = children
+        .iter()
+        .zip(items)
+        .map(|(child, &(_, sides, _))| {
+            let (_, _, cross_start, cross_end) = item_margin(sides);
+            main_cross(child.size(), vertical).1 + cross_start + cross_end
+        })
+        .fold(min_cross.max(above + below), f32::max);
+
+    let (margin_main, margin_cross) = if vertical {
+        (margin.top, margin.left)
+    } else {
+        (margin.left, margin.top)
+    };
+
+    let mut pos = margin_main;
+    for (i, child) in children.iter_mut().enumerate() {
+        let (align, sides, _) = items[i];
+        let (main_start, main_end, cross_start, cross_end) = item_margin(sides);
+        let (child_main, child_cross) = main_cross(sizes[i], vertical);
+        let available = cross - cross_start - cross_end;
+
+        pos += main_start;
+
+        let offset = match align {
+            Align::Begin => 0.,
+            Align::Baseline if !vertical => above - cross_start - baseline_of(child),
+            Align::Baseline => 0.,
+            Align::Middle => (available - child_cross) / 2.,
+            Align::End => available - child_cross,
+            Align::Fill => {
+                child.layout(BoxConstraints::tight(from_main_cross(
+                    child_main,
+                    available.max(0.),
+                    vertical,
+                )));
+                0.
             }
+        };
 
-            let x = match item.align {
-                Align::Begin => rect.origin.x,
-                Align::Middle => rect.origin.x + (rect.size.width / 2.) - (size.width / 2.),
-                Align::End => rect.origin.x + rect.size.width - size.width,
-                Align::Fill => {
-                    size.width = rect.size.width;
-                    rect.origin.x
-                }
-            };
+        let position = from_main_cross(pos, margin_cross + cross_start + offset, vertical);
+        child.set_position(point2(position.width, position.height));
 
-            out.push(Rect::new(point2(x, y), size));
-            y += size.height + item.margin.bottom + self.spacing;
-        }
-        out
+        pos += child_main + main_end + spacing;
     }
+
+    let size = from_main_cross(main, cross, vertical);
+    constraints.constrain(size2(
+        size.width + margin.horizontal(),
+        size.height + margin.vertical(),
+    ))
 }
 
 struct StackLayout {
@@ -154,13 +223,21 @@ struct StackLayout {
     height: Option<f32>,
 }
 
-impl Layout for StackLayout {
-    fn size(&self, sizes: &[Size2]) -> Size2 {
-        let mut size: Size2 = size2(0., 0.);
+impl BoxLayout for StackLayout {
+    fn layout(&self, constraints: BoxConstraints, children: &mut [ResolvedNode]) -> Size2 {
+        let inner = constraints.deflate(self.margin);
 
-        for sz in sizes {
-            size.width = size.width.max(sz.width);
-            size.height = size.height.max(sz.height);
+        // every child counts towards the size of the stack at its natural size, even those which are then sized relative to the stack
+        let mut sizes = vec![None; children.len()];
+        let mut size: Size2 = size2(0., 0.);
+        for (i, child) in children.iter_mut().enumerate() {
+            let item = &self.items[i];
+            let child_size = child.layout(inner.loosen());
+            size.width = size.width.max(child_size.width);
+            size.height = size.height.max(child_size.height);
+            if item.width.is_none() && item.height.is_none() {
+                sizes[i] = Some(child_size);
+            }
         }
 
         if let Some(width) = self.width {
@@ -171,46 +248,39 @@ impl Layout for StackLayout {
             size.height = height;
         }
 
-        size2(
-            size.width + self.margin.horizontal(),
-            size.height + self.margin.vertical(),
-        )
-    }
-
-    fn position(&self, rect: Rect, sizes: &[Size2]) -> Vec<Rect> {
-        let rect = rect.inner_rect(self.margin);
-
-        let mut out = Vec::new();
+        let size = inner.constrain(size);
 
-        for (i, &size) in sizes.iter().enumerate() {
-            //let mut size = size2(0., 0.);
-            let mut size = size;
+        for (i, child) in children.iter_mut().enumerate() {
             let item = &self.items[i];
 
-            if let Some(w) = item.width {
-                size.width = w * rect.size.width;
-            }
-
-            if let Some(h) = item.height {
-                size.height = h * rect.size.height;
-            }
-
-            if let Some(wh) = item.wh_offset {
-                size += wh;
-            }
-
-            let mut xy = point2(item.xy.x * rect.size.width, item.xy.y * rect.size.height);
-            xy += item.xy_offset.to_vector() + rect.origin.to_vector();
+            let child_size = sizes[i].unwrap_or_else(|| {
+                let offset = item.wh_offset.unwrap_or_default();
+                let width = item.width.map(|w| (w * size.width + offset.width).max(0.));
+                let height = item
+                    .height
+                    .map(|h| (h * size.height + offset.height).max(0.));
+                child.layout(BoxConstraints::new(
+                    size2(width.unwrap_or(0.), height.unwrap_or(0.)),
+                    size2(width.unwrap_or(size.width), height.unwrap_or(size.height)),
+                ))
+            });
+
+            let mut xy = point2(item.xy.x * size.width, item.xy.y * size.height);
+            xy +=
+                item.xy_offset.to_vector() + point2(self.margin.left, self.margin.top).to_vector();
             xy -= point2(
-                item.xy_anchor.x * size.width,
-                item.xy_anchor.y * size.height,
+                item.xy_anchor.x * child_size.width,
+                item.xy_anchor.y * child_size.height,
             )
             .to_vector();
 
-            out.push(Rect::new(xy, size));
+            child.set_position(xy);
         }
 
-        out
+        constraints.constrain(size2(
+            size.width + self.margin.horizontal(),
+            size.height + self.margin.vertical(),
+        ))
     }
 }
 
@@ -218,27 +288,87 @@ struct ContainerLayout {
     margin: Sides2,
 }
 
-impl Layout for ContainerLayout {
-    fn size(&self, sizes: &[Size2]) -> Size2 {
-        let size = if !sizes.is_empty() {
-            assert!(sizes.len() == 1);
-            sizes[0]
-        } else {
-            size2(0., 0.)
-        };
-        size2(
+impl BoxLayout for ContainerLayout {
+    fn layout(&self, constraints: BoxConstraints, children: &mut [ResolvedNode]) -> Size2 {
+        let size = children
+            .first_mut()
+            .map(|child| {
+                let size = child.layout(constraints.deflate(self.margin));
+                child.set_position(point2(self.margin.left, self.margin.top));
+                size
+            })
+            .unwrap_or_else(|| size2(0., 0.));
+
+        constraints.constrain(size2(
             size.width + self.margin.horizontal(),
             size.height + self.margin.vertical(),
-        )
+        ))
+    }
+}
+
+struct SizedBoxLayout {
+    min: Size2,
+    max: Size2,
+    aspect_ratio: Option<f32>,
+}
+
+impl SizedBoxLayout {
+    /// Narrows `constraints` to the limits of this box; where the two disagree, `constraints` wins.
+    fn constraints(&self, constraints: BoxConstraints) -> BoxConstraints {
+        let min = constraints.constrain(self.min);
+        BoxConstraints::new(min, constraints.constrain(self.max).max(min))
     }
+}
+
+impl BoxLayout for SizedBoxLayout {
+    fn layout(&self, constraints: BoxConstraints, children: &mut [ResolvedNode]) -> Size2 {
+        let constraints = self.constraints(constraints);
+
+        let ratio = match self.aspect_ratio {
+            Some(ratio) if ratio > 0. => ratio,
+            _ => {
+                return children
+                    .first_mut()
+                    .map(|child| {
+                        let size = child.layout(constraints);
+                        child.set_position(Point2::zero());
+                        size
+                    })
+                    .map_or(constraints.min, |size| constraints.constrain(size));
+            }
+        };
 
-    fn position(&self, rect: Rect, sizes: &[Size2]) -> Vec<Rect> {
-        if !sizes.is_empty() {
-            assert!(sizes.len() == 1);
-            vec![rect.inner_rect(self.margin)]
+        // take as much width as possible (or height, if width is unbounded), then correct for the remaining limits
+        let max = constraints.max;
+        let mut size = if max.width.is_finite() {
+            size2(max.width, max.width / ratio)
+        } else if max.height.is_finite() {
+            size2(max.height * ratio, max.height)
         } else {
-            vec![]
+            // entirely unbounded, so go by the natural width of the child
+            let width = children
+                .first_mut()
+                .map_or(0., |child| child.layout(constraints).width);
+            size2(width, width / ratio)
+        };
+
+        if size.height > max.height {
+            size = size2(max.height * ratio, max.height);
+        }
+        if size.width < constraints.min.width {
+            size = size2(constraints.min.width, constraints.min.width / ratio);
+        }
+        if size.height < constraints.min.height {
+            size = size2(constraints.min.height * ratio, constraints.min.height);
         }
+        let size = constraints.constrain(size);
+
+        if let Some(child) = children.first_mut() {
+            child.layout(BoxConstraints::tight(size));
+            child.set_position(Point2::zero());
+        }
+
+        size
     }
 }
 
@@ -248,6 +378,10 @@ pub enum Align {
     Middle,
     End,
     Fill,
+    /// Lines up the first baseline of every baseline-aligned child, falling back to the bottom edge of children without text.
+    ///
+    /// Only rows and wraps align by baseline; elsewhere this is the same as `Begin`.
+    Baseline,
 }
 
 impl Default for Align {
@@ -666,3 +800,1694 @@ impl IntoNode for Container {
         }
     }
 }
+
+/// Limits the size of its child, e.g. "at least 120px, at most 400px wide" or "always 16:9".
+///
+/// The limits are applied within the constraints of the parent, which take precedence.
+pub struct SizedBox {
+    children: Vec<Node>,
+    items: Vec<()>,
+
+    min: Size2,
+    max: Size2,
+    aspect_ratio: Option<f32>,
+}
+
+impl Default for SizedBox {
+    fn default() -> Self {
+        SizedBox {
+            children: Vec::new(),
+            items: Vec::new(),
+            min: Size2::zero(),
+            max: size2(f32::INFINITY, f32::INFINITY),
+            aspect_ratio: None,
+        }
+    }
+}
+
+impl SizedBox {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn min_width(mut self, min_width: f32) -> Self {
+        self.min.width = min_width;
+        self
+    }
+
+    pub fn max_width(mut self, max_width: f32) -> Self {
+        self.max.width = max_width;
+        self
+    }
+
+    pub fn min_height(mut self, min_height: f32) -> Self {
+        self.min.height = min_height;
+        self
+    }
+
+    pub fn max_height(mut self, max_height: f32) -> Self {
+        self.max.height = max_height;
+        self
+    }
+
+    /// Shorthand for setting both the minimum and maximum width.
+    pub fn width(self, width: f32) -> Self {
+        self.min_width(width).max_width(width)
+    }
+
+    /// Shorthand for setting both the minimum and maximum height.
+    pub fn height(self, height: f32) -> Self {
+        self.min_height(height).max_height(height)
+    }
+
+    /// Keeps width divided by height at `aspect_ratio` (e.g. `16. / 9.`), as far as the other limits allow.
+    pub fn aspect_ratio(mut self, aspect_ratio: impl Into<Option<f32>>) -> Self {
+        self.aspect_ratio = aspect_ratio.into();
+        self
+    }
+}
+
+impl LayoutBuilder for SizedBox {
+    type Item = ();
+
+    fn max_children() -> usize {
+        1
+    }
+
+    fn get_children(&mut self) -> &mut Vec<Node> {
+        &mut self.children
+    }
+
+    fn get_items(&mut self) -> &mut Vec<Self::Item> {
+        &mut self.items
+    }
+}
+
+impl IntoNode for SizedBox {
+    #[ui]
+    fn into_node(self) -> Node {
+        Node::Layout {
+            layout: Rc::new(SizedBoxLayout {
+                min: self.min,
+                max: self.max,
+                aspect_ratio: self.aspect_ratio,
+            }),
+            children: self.children,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FlexDirection {
+    Row,
+    RowReverse,
+    Column,
+    ColumnReverse,
+}
+
+impl Default for FlexDirection {
+    fn default() -> Self {
+        FlexDirection::Row
+    }
+}
+
+impl FlexDirection {
+    fn is_vertical(self) -> bool {
+        matches!(self, FlexDirection::Column | FlexDirection::ColumnReverse)
+    }
+
+    fn is_reverse(self) -> bool {
+        matches!(
+            self,
+            FlexDirection::RowReverse | FlexDirection::ColumnReverse
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FlexWrap {
+    NoWrap,
+    Wrap,
+    /// Wraps, stacking lines from the cross end towards the cross start.
+    WrapReverse,
+}
+
+impl Default for FlexWrap {
+    fn default() -> Self {
+        FlexWrap::NoWrap
+    }
+}
+
+/// Distribution of free space along the main axis of each line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum JustifyContent {
+    Start,
+    Center,
+    End,
+    SpaceBetween,
+    SpaceAround,
+    SpaceEvenly,
+}
+
+impl Default for JustifyContent {
+    fn default() -> Self {
+        JustifyContent::Start
+    }
+}
+
+impl JustifyContent {
+    /// Returns the offset of the first of `count` elements and the extra space between consecutive elements, given `free` space.
+    ///
+    /// As in CSS, the `Space*` variants fall back to `Start` when there is no free space.
+    fn distribute(self, free: f32, count: usize) -> (f32, f32) {
+        let count = count as f32;
+        match self {
+            JustifyContent::Center => (free / 2., 0.),
+            JustifyContent::End => (free, 0.),
+            _ if free <= 0. => (0., 0.),
+            JustifyContent::SpaceBetween if count > 1. => (0., free / (count - 1.)),
+            JustifyContent::SpaceAround => (free / count / 2., free / count),
+            JustifyContent::SpaceEvenly => (free / (count + 1.), free / (count + 1.)),
+            _ => (0., 0.),
+        }
+    }
+}
+
+/// Alignment of items along the cross axis of their line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AlignItems {
+    Start,
+    Center,
+    End,
+    Stretch,
+}
+
+impl Default for AlignItems {
+    fn default() -> Self {
+        AlignItems::Stretch
+    }
+}
+
+/// Distribution of free space along the cross axis between lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AlignContent {
+    Start,
+    Center,
+    End,
+    SpaceBetween,
+    SpaceAround,
+    SpaceEvenly,
+    /// Free space is split evenly between the lines.
+    Stretch,
+}
+
+impl Default for AlignContent {
+    fn default() -> Self {
+        AlignContent::Stretch
+    }
+}
+
+/// Initial main size of a flex item, before growing or shrinking.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FlexBasis {
+    /// The natural size of the item.
+    Auto,
+    Points(f32),
+    /// Fraction of the main size of the container; treated as `Auto` if the container is unbounded.
+    Fraction(f32),
+}
+
+impl Default for FlexBasis {
+    fn default() -> Self {
+        FlexBasis::Auto
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlexItem {
+    pub grow: f32,
+    pub shrink: f32,
+    pub basis: FlexBasis,
+    /// Overrides `Flex::align_items` for this item.
+    pub align_self: Option<AlignItems>,
+    pub margin: Sides2,
+}
+
+impl Default for FlexItem {
+    fn default() -> Self {
+        FlexItem {
+            grow: 0.,
+            shrink: 1.,
+            basis: FlexBasis::Auto,
+            align_self: None,
+            margin: Sides2::zero(),
+        }
+    }
+}
+
+impl FlexItem {
+    /// Item which grows by `grow` shares of the free space, starting from nothing (i.e. `flex: <grow>` in CSS).
+    pub fn flex(grow: f32) -> Self {
+        FlexItem {
+            grow,
+            basis: FlexBasis::Points(0.),
+            ..Default::default()
+        }
+    }
+}
+
+struct FlexLayout {
+    items: Vec<FlexItem>,
+    margin: Sides2,
+    direction: FlexDirection,
+    wrap: FlexWrap,
+    justify_content: JustifyContent,
+    align_items: AlignItems,
+    align_content: AlignContent,
+    row_gap: f32,
+    column_gap: f32,
+}
+
+impl FlexLayout {
+    /// Margins of `item`, as (main start, main end, cross start, cross end) in the direction items are laid out in.
+    fn item_margin(&self, item: &FlexItem) -> (f32, f32, f32, f32) {
+        let (mut main_start, mut main_end, mut cross_start, mut cross_end) =
+            sides_main_cross(item.margin, self.direction.is_vertical());
+        // positions are mirrored afterwards for reversed axes, so swap the margins to keep them on the right side
+        if self.direction.is_reverse() {
+            std::mem::swap(&mut main_start, &mut main_end);
+        }
+        if self.wrap == FlexWrap::WrapReverse {
+            std::mem::swap(&mut cross_start, &mut cross_end);
+        }
+        (main_start, main_end, cross_start, cross_end)
+    }
+
+    /// Outer main size of the items in `line`, given their main sizes.
+    fn line_main(&self, line: Range<usize>, mains: &[f32], gap: f32) -> f32 {
+        let gaps = gap * line.len().saturating_sub(1) as f32;
+        line.map(|i| {
+            let (main_start, main_end, _, _) = self.item_margin(&self.items[i]);
+            mains[i] + main_start + main_end
+        })
+        .sum::<f32>()
+            + gaps
+    }
+}
+
+impl BoxLayout for FlexLayout {
+    fn layout(&self, constraints: BoxConstraints, children: &mut [ResolvedNode]) -> Size2 {
+        let vertical = self.direction.is_vertical();
+        let inner = constraints.deflate(self.margin);
+        let (max_main, max_cross) = main_cross(inner.max, vertical);
+        let (min_main, min_cross) = main_cross(inner.min, vertical);
+        let (main_gap, cross_gap) = if vertical {
+            (self.row_gap, self.column_gap)
+        } else {
+            (self.column_gap, self.row_gap)
+        };
+
+        // flex base sizes
+        let mut base = Vec::with_capacity(children.len());
+        for (item, child) in self.items.iter().zip(children.iter_mut()) {
+            let (_, _, cross_start, cross_end) = self.item_margin(item);
+            let size = match item.basis {
+                FlexBasis::Points(size) => size,
+                FlexBasis::Fraction(fraction) if max_main.is_finite() => fraction * max_main,
+                _ => {
+                    let cross = (max_cross - cross_start - cross_end).max(0.);
+                    let size = child.layout(BoxConstraints::loose(from_main_cross(
+                        f32::INFINITY,
+                        cross,
+                        vertical,
+                    )));
+                    main_cross(size, vertical).0
+                }
+            };
+            base.push(size.max(0.));
+        }
+
+        // break into lines
+        let wrap = self.wrap != FlexWrap::NoWrap && max_main.is_finite();
+        let mut lines = Vec::new();
+        let mut start = 0;
+        for i in 0..children.len() {
+            if wrap && i > start && self.line_main(start..i + 1, &base, main_gap) > max_main {
+                lines.push(start..i);
+                start = i;
+            }
+        }
+        if start < children.len() {
+            lines.push(start..children.len());
+        }
+
+        // as in CSS, rows take the available width while columns wrap their content, unless there are items to grow into the space
+        let grows = self.items.iter().any(|item| item.grow > 0.);
+        let main = if max_main.is_finite() && (grows || !vertical) {
+            max_main
+        } else {
+            lines
+                .iter()
+                .map(|line| self.line_main(line.clone(), &base, main_gap))
+                .fold(min_main, f32::max)
+                .min(max_main)
+        };
+
+        // grow or shrink items to fit each line, then measure the cross size of each line
+        let mut mains = base.clone();
+        let mut line_crosses = Vec::with_capacity(lines.len());
+        for line in &lines {
+            let free = main - self.line_main(line.clone(), &base, main_gap);
+            let items = &self.items[line.clone()];
+
+            if free > 0. {
+                let grow = items.iter().map(|item| item.grow).sum::<f32>();
+                if grow > 0. {
+                    for i in line.clone() {
+                        // as in CSS, factors summing to less than one only take their share of the free space
+                        mains[i] += free * self.items[i].grow / grow.max(1.);
+                    }
+                }
+            } else if free < 0. {
+                // items don't shrink past their minimum, which is their size without any room along the main axis
+                let mut mins = vec![0.; children.len()];
+                for i in line.clone() {
+                    let (_, _, cross_start, cross_end) = self.item_margin(&self.items[i]);
+                    let cross = (max_cross - cross_start - cross_end).max(0.);
+                    let size = children[i]
+                        .layout(BoxConstraints::loose(from_main_cross(0., cross, vertical)));
+                    mins[i] = main_cross(size, vertical).0.min(base[i]);
+                }
+
+                // as in CSS, items which hit their minimum are frozen there, and the others share the rest of the shrinking
+                let mut frozen = vec![false; children.len()];
+                let mut targets = base.clone();
+                loop {
+                    let free = main - self.line_main(line.clone(), &targets, main_gap);
+                    let shrink = line
+                        .clone()
+                        .filter(|&i| !frozen[i])
+                        .map(|i| self.items[i].shrink * base[i])
+                        .sum::<f32>();
+                    if shrink <= 0. {
+                        break;
+                    }
+
+                    let mut clamped = false;
+                    for i in line.clone().filter(|&i| !frozen[i]) {
+                        mains[i] = base[i] + free * self.items[i].shrink * base[i] / shrink;
+                        if mains[i] < mins[i] {
+                            mains[i] = mins[i];
+                            targets[i] = mins[i];
+                            frozen[i] = true;
+                            clamped = true;
+                        }
+                    }
+                    if !clamped {
+                        break;
+                    }
+                }
+            }
+
+            let mut line_cross = 0f32;
+            for i in line.clone() {
+                let (_, _, cross_start, cross_end) = self.item_margin(&self.items[i]);
+                let cross = (max_cross - cross_start - cross_end).max(0.);
+                let size = children[i].layout(BoxConstraints::new(
+                    from_main_cross(mains[i], 0., vertical),
+                    from_main_cross(mains[i], cross, vertical),
+                ));
+                line_cross = line_cross.max(main_cross(size, vertical).1 + cross_start + cross_end);
+            }
+            line_crosses.push(line_cross);
+        }
+
+        // a single line fills the minimum cross size of the container
+        if self.wrap == FlexWrap::NoWrap {
+            if let Some(line_cross) = line_crosses.first_mut() {
+                *line_cross = line_cross.max(min_cross);
+            }
+        }
+
+        let lines_cross = line_crosses.iter().sum::<f32>()
+            + cross_gap * line_crosses.len().saturating_sub(1) as f32;
+        let cross = lines_cross.max(min_cross);
+        let free_cross = cross - lines_cross;
+
+        let (mut cross_pos, cross_between) = match self.align_content {
+            AlignContent::Stretch => {
+                if free_cross > 0. {
+                    let extra = free_cross / line_crosses.len().max(1) as f32;
+                    for line_cross in &mut line_crosses {
+                        *line_cross += extra;
+                    }
+                }
+                (0., 0.)
+            }
+            AlignContent::Start => JustifyContent::Start.distribute(free_cross, lines.len()),
+            AlignContent::Center => JustifyContent::Center.distribute(free_cross, lines.len()),
+            AlignContent::End => JustifyContent::End.distribute(free_cross, lines.len()),
+            AlignContent::SpaceBetween => {
+                JustifyContent::SpaceBetween.distribute(free_cross, lines.len())
+            }
+            AlignContent::SpaceAround => {
+                JustifyContent::SpaceAround.distribute(free_cross, lines.len())
+            }
+            AlignContent::SpaceEvenly => {
+                JustifyContent::SpaceEvenly.distribute(free_cross, lines.len())
+            }
+        };
+
+        let (margin_main, margin_cross) = if vertical {
+            (self.margin.top, self.margin.left)
+        } else {
+            (self.margin.left, self.margin.top)
+        };
+
+        for (line, &line_cross) in lines.iter().zip(&line_crosses) {
+            let free = main - self.line_main(line.clone(), &mains, main_gap);
+            let (mut main_pos, main_between) = self.justify_content.distribute(free, line.len());
+
+            for i in line.clone() {
+                let item = &self.items[i];
+                let (main_start, main_end, cross_start, cross_end) = self.item_margin(item);
+                let child = &mut children[i];
+                let available = line_cross - cross_start - cross_end;
+
+                let mut child_cross = main_cross(child.size(), vertical).1;
+                let offset = match item.align_self.unwrap_or(self.align_items) {
+                    AlignItems::Start => 0.,
+                    AlignItems::Center => (available - child_cross) / 2.,
+                    AlignItems::End => available - child_cross,
+                    AlignItems::Stretch => {
+                        let size = child.layout(BoxConstraints::tight(from_main_cross(
+                            mains[i],
+                            available.max(0.),
+                            vertical,
+                        )));
+                        child_cross = main_cross(size, vertical).1;
+                        0.
+                    }
+                };
+
+                main_pos += main_start;
+
+                let mut item_main = main_pos;
+                let mut item_cross = cross_pos + cross_start + offset;
+                if self.direction.is_reverse() {
+                    item_main = main - item_main - mains[i];
+                }
+                if self.wrap == FlexWrap::WrapReverse {
+                    item_cross = cross - item_cross - child_cross;
+                }
+
+                let position =
+                    from_main_cross(margin_main + item_main, margin_cross + item_cross, vertical);
+                child.set_position(point2(position.width, position.height));
+
+                main_pos += mains[i] + main_end + main_gap + main_between;
+            }
+
+            cross_pos += line_cross + cross_gap + cross_between;
+        }
+
+        let size = from_main_cross(main, cross, vertical);
+        constraints.constrain(size2(
+            size.width + self.margin.horizontal(),
+            size.height + self.margin.vertical(),
+        ))
+    }
+}
+
+/// CSS-style flexbox layout.
+///
+/// Along the main axis, rows take all the available space (if bounded), while columns wrap their content unless an item grows to fill the available space.
+/// Items shrink to fit a line, but not past their size without any room along the main axis.
+#[derive(Default)]
+pub struct Flex {
+    children: Vec<Node>,
+    items: Vec<FlexItem>,
+
+    margin: Sides2,
+    direction: FlexDirection,
+    wrap: FlexWrap,
+    justify_content: JustifyContent,
+    align_items: AlignItems,
+    align_content: AlignContent,
+    row_gap: f32,
+    column_gap: f32,
+}
+
+impl Flex {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn margin(mut self, margin: Sides2) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    pub fn direction(mut self, direction: FlexDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub fn wrap(mut self, wrap: FlexWrap) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    pub fn justify_content(mut self, justify_content: JustifyContent) -> Self {
+        self.justify_content = justify_content;
+        self
+    }
+
+    pub fn align_items(mut self, align_items: AlignItems) -> Self {
+        self.align_items = align_items;
+        self
+    }
+
+    pub fn align_content(mut self, align_content: AlignContent) -> Self {
+        self.align_content = align_content;
+        self
+    }
+
+    /// Sets both the row and column gap.
+    pub fn gap(self, gap: f32) -> Self {
+        self.row_gap(gap).column_gap(gap)
+    }
+
+    /// Space between rows, i.e. between lines of a row flex or between items of a column flex.
+    pub fn row_gap(mut self, row_gap: f32) -> Self {
+        self.row_gap = row_gap;
+        self
+    }
+
+    /// Space between columns, i.e. between items of a row flex or between lines of a column flex.
+    pub fn column_gap(mut self, column_gap: f32) -> Self {
+        self.column_gap = column_gap;
+        self
+    }
+}
+
+impl LayoutBuilder for Flex {
+    type Item = FlexItem;
+
+    fn get_children(&mut self) -> &mut Vec<Node> {
+        &mut self.children
+    }
+
+    fn get_items(&mut self) -> &mut Vec<Self::Item> {
+        &mut self.items
+    }
+}
+
+impl IntoNode for Flex {
+    #[ui]
+    fn into_node(self) -> Node {
+        Node::Layout {
+            layout: Rc::new(FlexLayout {
+                items: self.items,
+                margin: self.margin,
+                direction: self.direction,
+                wrap: self.wrap,
+                justify_content: self.justify_content,
+                align_items: self.align_items,
+                align_content: self.align_content,
+                row_gap: self.row_gap,
+                column_gap: self.column_gap,
+            }),
+            children: self.children,
+        }
+    }
+}
+
+/// Size of a grid row or column.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Track {
+    /// Exactly this many logical pixels.
+    Fixed(f32),
+    /// Share of the space left over by the other tracks (i.e. `fr` in CSS); treated as `Auto` if the grid is unbounded along this axis.
+    Fraction(f32),
+    /// Fits the largest item within the track.
+    Auto,
+}
+
+impl Default for Track {
+    fn default() -> Self {
+        Track::Auto
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GridItem {
+    /// Row to place the item in; if `None`, the item is placed in the next free cell.
+    pub row: Option<usize>,
+    /// Column to place the item in; if `None`, the item is placed in the next free cell.
+    pub column: Option<usize>,
+    pub row_span: usize,
+    pub column_span: usize,
+    /// Horizontal alignment within the cell.
+    pub align_x: Align,
+    /// Vertical alignment within the cell.
+    pub align_y: Align,
+}
+
+impl Default for GridItem {
+    fn default() -> Self {
+        GridItem {
+            row: None,
+            column: None,
+            row_span: 1,
+            column_span: 1,
+            align_x: Align::Fill,
+            align_y: Align::Fill,
+        }
+    }
+}
+
+impl GridItem {
+    pub fn at(row: usize, column: usize) -> Self {
+        GridItem {
+            row: Some(row),
+            column: Some(column),
+            ..Default::default()
+        }
+    }
+
+    pub fn span(mut self, rows: usize, columns: usize) -> Self {
+        self.row_span = rows;
+        self.column_span = columns;
+        self
+    }
+
+    pub fn align(mut self, x: Align, y: Align) -> Self {
+        self.align_x = x;
+        self.align_y = y;
+        self
+    }
+}
+
+/// Cell of a placed grid item, as (row, column, row span, column span).
+type GridCell = (usize, usize, usize, usize);
+
+struct GridLayout {
+    items: Vec<GridItem>,
+    margin: Sides2,
+    columns: Vec<Track>,
+    rows: Vec<Track>,
+    auto_rows: Track,
+    row_gap: f32,
+    column_gap: f32,
+}
+
+impl GridLayout {
+    /// Places items with an explicit row and column first, then the rest into the first free cells in row-major order.
+    fn place(&self, columns: usize) -> Vec<GridCell> {
+        let mut occupied: Vec<Vec<bool>> = Vec::new();
+        let is_free = |occupied: &Vec<Vec<bool>>,
+                       (row, column, row_span, column_span): GridCell| {
+            (row..row + row_span).all(|row| {
+                occupied.get(row).map_or(true, |cells| {
+                    cells[column..column + column_span].iter().all(|cell| !cell)
+                })
+            })
+        };
+        let occupy = |occupied: &mut Vec<Vec<bool>>,
+                      (row, column, row_span, column_span): GridCell| {
+            if occupied.len() < row + row_span {
+                occupied.resize(row + row_span, vec![false; columns]);
+            }
+            for cells in &mut occupied[row..row + row_span] {
+                for cell in &mut cells[column..column + column_span] {
+                    *cell = true;
+                }
+            }
+        };
+
+        let spans = |item: &GridItem| {
+            let column_span = item.column_span.clamp(1, columns);
+            (item.row_span.max(1), column_span, columns - column_span)
+        };
+
+        let mut cells = vec![None; self.items.len()];
+
+        for (i, item) in self.items.iter().enumerate() {
+            if let (Some(row), Some(column)) = (item.row, item.column) {
+                let (row_span, column_span, last_column) = spans(item);
+                let cell = (row, column.min(last_column), row_span, column_span);
+                occupy(&mut occupied, cell);
+                cells[i] = Some(cell);
+            }
+        }
+
+        let mut cursor = (0, 0);
+        for (i, item) in self.items.iter().enumerate() {
+            if cells[i].is_some() {
+                continue;
+            }
+
+            let (row_span, column_span, last_column) = spans(item);
+            let cell = match (item.row, item.column) {
+                (Some(row), None) => (0..=last_column)
+                    .map(|column| (row, column, row_span, column_span))
+                    .find(|&cell| is_free(&occupied, cell))
+                    .unwrap_or((row, 0, row_span, column_span)),
+                (None, Some(column)) => (cursor.0..)
+                    .map(|row| (row, column.min(last_column), row_span, column_span))
+                    .find(|&cell| is_free(&occupied, cell))
+                    .unwrap(),
+                _ => {
+                    let (mut row, mut column) = cursor;
+                    loop {
+                        if column > last_column {
+                            row += 1;
+                            column = 0;
+                        } else if is_free(&occupied, (row, column, row_span, column_span)) {
+                            break;
+                        } else {
+                            column += 1;
+                        }
+                    }
+                    cursor = (row, column + column_span);
+                    (row, column, row_span, column_span)
+                }
+            };
+
+            occupy(&mut occupied, cell);
+            cells[i] = Some(cell);
+        }
+
+        cells.into_iter().map(Option::unwrap).collect()
+    }
+}
+
+/// Sizes `tracks` along one axis, given the (first track, span, natural size) of every item and the `available` space.
+fn size_tracks(
+    tracks: &[Track],
+    items: &[(usize, usize, f32)],
+    gap: f32,
+    available: f32,
+) -> Vec<f32> {
+    let is_auto = |track: &Track| match track {
+        Track::Fixed(_) => false,
+        Track::Fraction(_) => !available.is_finite(),
+        Track::Auto => true,
+    };
+
+    let mut sizes = tracks
+        .iter()
+        .map(|track| match track {
+            Track::Fixed(size) => *size,
+            _ => 0.,
+        })
+        .collect::<Vec<_>>();
+
+    // items within a single track go first, then spanning items grow the auto tracks they span if they don't fit already
+    let mut items = items.to_vec();
+    items.sort_by_key(|&(_, span, _)| span);
+    for (start, span, size) in items {
+        let auto = (start..start + span)
+            .filter(|&i| is_auto(&tracks[i]))
+            .collect::<Vec<_>>();
+        let current = sizes[start..start + span].iter().sum::<f32>() + gap * (span - 1) as f32;
+        if !auto.is_empty() && size > current {
+            let extra = (size - current) / auto.len() as f32;
+            for i in auto {
+                sizes[i] += extra;
+            }
+        }
+    }
+
+    // fractions share whatever space is left
+    if available.is_finite() {
+        let fractions = tracks
+            .iter()
+            .map(|track| match track {
+                Track::Fraction(fraction) => *fraction,
+                _ => 0.,
+            })
+            .sum::<f32>();
+
+        if fractions > 0. {
+            let used = sizes.iter().sum::<f32>() + gap * tracks.len().saturating_sub(1) as f32;
+            let free = (available - used).max(0.);
+            for (size, track) in sizes.iter_mut().zip(tracks) {
+                if let Track::Fraction(fraction) = track {
+                    *size = free * fraction / fractions;
+                }
+            }
+        }
+    }
+
+    sizes
+}
+
+/// Returns the offset of the track at `index` and the size of `span` tracks from there.
+fn track_span(sizes: &[f32], index: usize, span: usize, gap: f32) -> (f32, f32) {
+    let offset = sizes[..index].iter().sum::<f32>() + gap * index as f32;
+    let size = sizes[index..index + span].iter().sum::<f32>() + gap * (span - 1) as f32;
+    (offset, size)
+}
+
+/// Offset of a child of `size` within `available` space.
+fn align_offset(align: Align, available: f32, size: f32) -> f32 {
+    match align {
+        Align::Begin | Align::Fill | Align::Baseline => 0.,
+        Align::Middle => (available - size) / 2.,
+        Align::End => available - size,
+    }
+}
+
+impl BoxLayout for GridLayout {
+    fn layout(&self, constraints: BoxConstraints, children: &mut [ResolvedNode]) -> Size2 {
+        let inner = constraints.deflate(self.margin);
+
+        let columns = if self.columns.is_empty() {
+            vec![Track::Auto]
+        } else {
+            self.columns.clone()
+        };
+        let cells = self.place(columns.len());
+
+        // rows beyond those given are implicit
+        let row_count = cells
+            .iter()
+            .map(|&(row, _, row_span, _)| row + row_span)
+            .fold(self.rows.len(), usize::max);
+        let rows = (0..row_count)
+            .map(|i| self.rows.get(i).copied().unwrap_or(self.auto_rows))
+            .collect::<Vec<_>>();
+
+        let widths = children
+            .iter_mut()
+            .zip(&cells)
+            .map(|(child, &(_, column, _, column_span))| {
+                let size = child.layout(BoxConstraints::unbounded());
+                (column, column_span, size.width)
+            })
+            .collect::<Vec<_>>();
+        let column_sizes = size_tracks(&columns, &widths, self.column_gap, inner.max.width);
+
+        // heights depend on the widths of the columns (e.g. for wrapped text)
+        let heights = children
+            .iter_mut()
+            .zip(&cells)
+            .map(|(child, &(row, column, row_span, column_span))| {
+                let (_, width) = track_span(&column_sizes, column, column_span, self.column_gap);
+                let size = child.layout(BoxConstraints::loose(size2(width, f32::INFINITY)));
+                (row, row_span, size.height)
+            })
+            .collect::<Vec<_>>();
+        let row_sizes = size_tracks(&rows, &heights, self.row_gap, inner.max.height);
+
+        for ((child, item), &(row, column, row_span, column_span)) in
+            children.iter_mut().zip(&self.items).zip(&cells)
+        {
+            let (x, width) = track_span(&column_sizes, column, column_span, self.column_gap);
+            let (y, height) = track_span(&row_sizes, row, row_span, self.row_gap);
+
+            let min = size2(
+                if item.align_x == Align::Fill {
+                    width
+                } else {
+                    0.
+                },
+                if item.align_y == Align::Fill {
+                    height
+                } else {
+                    0.
+                },
+            );
+            let size = child.layout(BoxConstraints::new(min, size2(width, height)));
+
+            child.set_position(point2(
+                self.margin.left + x + align_offset(item.align_x, width, size.width),
+                self.margin.top + y + align_offset(item.align_y, height, size.height),
+            ));
+        }
+
+        let (_, width) = track_span(&column_sizes, 0, column_sizes.len(), self.column_gap);
+        let height = if row_sizes.is_empty() {
+            0.
+        } else {
+            track_span(&row_sizes, 0, row_sizes.len(), self.row_gap).1
+        };
+
+        constraints.constrain(size2(
+            width + self.margin.horizontal(),
+            height + self.margin.vertical(),
+        ))
+    }
+}
+
+/// Two-dimensional layout of children into rows and columns.
+///
+/// Rows beyond those given with `rows` are sized with `auto_rows`.
+#[derive(Default)]
+pub struct Grid {
+    children: Vec<Node>,
+    items: Vec<GridItem>,
+
+    margin: Sides2,
+    columns: Vec<Track>,
+    rows: Vec<Track>,
+    auto_rows: Track,
+    row_gap: f32,
+    column_gap: f32,
+}
+
+impl Grid {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn margin(mut self, margin: Sides2) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    pub fn columns(mut self, columns: impl Into<Vec<Track>>) -> Self {
+        self.columns = columns.into();
+        self
+    }
+
+    pub fn rows(mut self, rows: impl Into<Vec<Track>>) -> Self {
+        self.rows = rows.into();
+        self
+    }
+
+    pub fn auto_rows(mut self, auto_rows: Track) -> Self {
+        self.auto_rows = auto_rows;
+        self
+    }
+
+    /// Sets both the row and column gap.
+    pub fn gap(self, gap: f32) -> Self {
+        self.row_gap(gap).column_gap(gap)
+    }
+
+    pub fn row_gap(mut self, row_gap: f32) -> Self {
+        self.row_gap = row_gap;
+        self
+    }
+
+    pub fn column_gap(mut self, column_gap: f32) -> Self {
+        self.column_gap = column_gap;
+        self
+    }
+}
+
+impl LayoutBuilder for Grid {
+    type Item = GridItem;
+
+    fn get_children(&mut self) -> &mut Vec<Node> {
+        &mut self.children
+    }
+
+    fn get_items(&mut self) -> &mut Vec<Self::Item> {
+        &mut self.items
+    }
+}
+
+impl IntoNode for Grid {
+    #[ui]
+    fn into_node(self) -> Node {
+        Node::Layout {
+            layout: Rc::new(GridLayout {
+                items: self.items,
+                margin: self.margin,
+                columns: self.columns,
+                rows: self.rows,
+                auto_rows: self.auto_rows,
+                row_gap: self.row_gap,
+                column_gap: self.column_gap,
+            }),
+            children: self.children,
+        }
+    }
+}
+
+/// Order in which `Wrap` places the children of each run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WrapDirection {
+    LeftToRight,
+    RightToLeft,
+}
+
+impl Default for WrapDirection {
+    fn default() -> Self {
+        WrapDirection::LeftToRight
+    }
+}
+
+struct WrapLayout {
+    margin: Sides2,
+    direction: WrapDirection,
+    spacing: f32,
+    run_spacing: f32,
+    justify: JustifyContent,
+    run_alignment: JustifyContent,
+    cross_alignment: Align,
+}
+
+struct WrapRun {
+    children: Range<usize>,
+    width: f32,
+    height: f32,
+    /// Extent of the tallest child above the baseline, when aligning by baseline.
+    baseline: f32,
+}
+
+impl WrapLayout {
+    fn baseline_of(child: &ResolvedNode) -> f32 {
+        child.baseline().unwrap_or_else(|| child.size().height)
+    }
+
+    fn runs(&self, max_width: f32, children: &[ResolvedNode]) -> Vec<WrapRun> {
+        let mut runs = Vec::<WrapRun>::new();
+
+        for (i, child) in children.iter().enumerate() {
+            let size = child.size();
+            match runs.last_mut() {
+                Some(run) if run.width + self.spacing + size.width <= max_width => {
+                    run.children.end = i + 1;
+                    run.width += self.spacing + size.width;
+                }
+                _ => runs.push(WrapRun {
+                    children: i..i + 1,
+                    width: size.width,
+                    height: 0.,
+                    baseline: 0.,
+                }),
+            }
+        }
+
+        for run in &mut runs {
+            let children = &children[run.children.clone()];
+            run.height = children
+                .iter()
+                .map(|child| child.size().height)
+                .fold(0., f32::max);
+
+            if self.cross_alignment == Align::Baseline {
+                let (above, below) = children.iter().fold((0f32, 0f32), |(above, below), child| {
+                    let baseline = Self::baseline_of(child);
+                    (
+                        above.max(baseline),
+                        below.max(child.size().height - baseline),
+                    )
+                });
+                run.baseline = above;
+                run.height = run.height.max(above + below);
+            }
+        }
+
+        runs
+    }
+}
+
+impl BoxLayout for WrapLayout {
+    fn layout(&self, constraints: BoxConstraints, children: &mut [ResolvedNode]) -> Size2 {
+        let inner = constraints.deflate(self.margin);
+
+        for child in children.iter_mut() {
+            child.layout(BoxConstraints::loose(size2(inner.max.width, f32::INFINITY)));
+        }
+
+        let runs = self.runs(inner.max.width, children);
+
+        let content_width = runs.iter().map(|run| run.width).fold(0., f32::max);
+        let content_height = runs.iter().map(|run| run.height).sum::<f32>()
+            + self.run_spacing * runs.len().saturating_sub(1) as f32;
+
+        let width = if inner.max.width.is_finite() {
+            inner.max.width
+        } else {
+            content_width.max(inner.min.width)
+        };
+        let height = content_height.max(inner.min.height);
+
+        let (mut y, run_between) = self
+            .run_alignment
+            .distribute(height - content_height, runs.len());
+
+        for run in &runs {
+            let (mut x, between) = self
+                .justify
+                .distribute(width - run.width, run.children.len());
+
+            for child in &mut children[run.children.clone()] {
+                let size = child.size();
+
+                let offset = match self.cross_alignment {
+                    Align::Begin => 0.,
+                    Align::Middle => (run.height - size.height) / 2.,
+                    Align::End => run.height - size.height,
+                    Align::Fill => {
+                        child.layout(BoxConstraints::tight(size2(size.width, run.height)));
+                        0.
+                    }
+                    Align::Baseline => run.baseline - Self::baseline_of(child),
+                };
+
+                let left = match self.direction {
+                    WrapDirection::LeftToRight => x,
+                    WrapDirection::RightToLeft => width - x - size.width,
+                };
+                child.set_position(point2(
+                    self.margin.left + left,
+                    self.margin.top + y + offset,
+                ));
+
+                x += size.width + self.spacing + between;
+            }
+
+            y += run.height + self.run_spacing + run_between;
+        }
+
+        constraints.constrain(size2(
+            width + self.margin.horizontal(),
+            height + self.margin.vertical(),
+        ))
+    }
+}
+
+/// Lays children out in runs, starting a new run below whenever the width is exhausted, like words in a paragraph.
+///
+/// Takes all the available width (if bounded), otherwise the width of the longest run.
+#[derive(Default)]
+pub struct Wrap {
+    children: Vec<Node>,
+    items: Vec<()>,
+
+    margin: Sides2,
+    direction: WrapDirection,
+    spacing: f32,
+    run_spacing: f32,
+    justify: JustifyContent,
+    run_alignment: JustifyContent,
+    cross_alignment: Align,
+}
+
+impl Wrap {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn margin(mut self, margin: Sides2) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    pub fn direction(mut self, direction: WrapDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Space between consecutive children of a run.
+    pub fn spacing(mut self, spacing: f32) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Space between consecutive runs.
+    pub fn run_spacing(mut self, run_spacing: f32) -> Self {
+        self.run_spacing = run_spacing;
+        self
+    }
+
+    /// Distribution of the free space of each run, along the run.
+    ///
+    /// `Start` and `End` follow the `direction`.
+    pub fn justify(mut self, justify: JustifyContent) -> Self {
+        self.justify = justify;
+        self
+    }
+
+    /// Distribution of free space between runs, if the wrap is taller than its runs.
+    pub fn run_alignment(mut self, run_alignment: JustifyContent) -> Self {
+        self.run_alignment = run_alignment;
+        self
+    }
+
+    /// Alignment of children within the height of their run.
+    pub fn cross_alignment(mut self, cross_alignment: Align) -> Self {
+        self.cross_alignment = cross_alignment;
+        self
+    }
+}
+
+impl LayoutBuilder for Wrap {
+    type Item = ();
+
+    fn get_children(&mut self) -> &mut Vec<Node> {
+        &mut self.children
+    }
+
+    fn get_items(&mut self) -> &mut Vec<Self::Item> {
+        &mut self.items
+    }
+}
+
+impl IntoNode for Wrap {
+    #[ui]
+    fn into_node(self) -> Node {
+        Node::Layout {
+            layout: Rc::new(WrapLayout {
+                margin: self.margin,
+                direction: self.direction,
+                spacing: self.spacing,
+                run_spacing: self.run_spacing,
+                justify: self.justify,
+                run_alignment: self.run_alignment,
+                cross_alignment: self.cross_alignment,
+            }),
+            children: self.children,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cape::Rect;
+
+    /// Leaf which prefers `size`, but can't get narrower than `min_width`.
+    struct Leaf {
+        size: Size2,
+        min_width: f32,
+        baseline: Option<f32>,
+    }
+
+    impl BoxLayout for Leaf {
+        fn layout(&self, constraints: BoxConstraints, _children: &mut [ResolvedNode]) -> Size2 {
+            let size = constraints.constrain(self.size);
+            size2(size.width.max(self.min_width), size.height)
+        }
+
+        fn baseline(&self, _children: &[ResolvedNode]) -> Option<f32> {
+            self.baseline
+        }
+    }
+
+    fn node(leaf: Leaf) -> ResolvedNode {
+        ResolvedNode::Layout {
+            layout: Rc::new(leaf),
+            children: Vec::new(),
+            exiting: Vec::new(),
+            baseline: None,
+            rect: Rect::default(),
+            cache: Default::default(),
+            stale: false,
+        }
+    }
+
+    fn leaf(width: f32, height: f32) -> ResolvedNode {
+        node(Leaf {
+            size: size2(width, height),
+            min_width: 0.,
+            baseline: None,
+        })
+    }
+
+    fn flex(direction: FlexDirection, items: Vec<FlexItem>) -> FlexLayout {
+        FlexLayout {
+            items,
+            margin: Sides2::zero(),
+            direction,
+            wrap: FlexWrap::NoWrap,
+            justify_content: JustifyContent::Start,
+            align_items: AlignItems::Stretch,
+            align_content: AlignContent::Stretch,
+            row_gap: 0.,
+            column_gap: 0.,
+        }
+    }
+
+    fn grid(items: Vec<GridItem>) -> GridLayout {
+        GridLayout {
+            items,
+            margin: Sides2::zero(),
+            columns: vec![Track::Auto; 2],
+            rows: Vec::new(),
+            auto_rows: Track::Auto,
+            row_gap: 0.,
+            column_gap: 0.,
+        }
+    }
+
+    #[test]
+    fn stack_counts_relatively_sized_children() {
+        let layout = StackLayout {
+            items: vec![StackItem::top_left(), StackItem::fill()],
+            margin: Sides2::zero(),
+            width: None,
+            height: None,
+        };
+        let mut children = vec![leaf(50., 20.), leaf(80., 10.)];
+        let size = layout.layout(BoxConstraints::unbounded(), &mut children);
+
+        // the filling child is wider than the other, so the stack takes its width before it fills the stack
+        assert_eq!(size, size2(80., 20.));
+        assert_eq!(children[0].size(), size2(50., 20.));
+        assert_eq!(children[1].size(), size2(80., 20.));
+    }
+
+    #[test]
+    fn row_aligns_children_by_baseline() {
+        let item = RowItem {
+            align: Align::Baseline,
+            ..Default::default()
+        };
+        let layout = RowLayout {
+            items: vec![
+                item,
+                RowItem {
+                    margin: Sides2::new(4., 0., 0., 0.),
+                    ..item
+                },
+                item,
+            ],
+            margin: Sides2::new(5., 0., 0., 0.),
+            spacing: 0.,
+        };
+        let mut children = vec![
+            node(Leaf {
+                size: size2(20., 30.),
+                min_width: 0.,
+                baseline: Some(20.),
+            }),
+            node(Leaf {
+                size: size2(20., 20.),
+                min_width: 0.,
+                baseline: Some(10.),
+            }),
+            // without a baseline, the bottom edge is used
+            leaf(20., 10.),
+        ];
+        let size = layout.layout(BoxConstraints::unbounded(), &mut children);
+
+        assert_eq!(size, size2(60., 35.));
+        assert_eq!(children[0].position(), point2(0., 5.));
+        assert_eq!(children[1].position(), point2(20., 15.));
+        assert_eq!(children[2].position(), point2(40., 15.));
+        assert_eq!(layout.baseline(&children), Some(25.));
+    }
+
+    fn aspect_ratio(min: Size2, ratio: f32) -> SizedBoxLayout {
+        SizedBoxLayout {
+            min,
+            max: size2(f32::INFINITY, f32::INFINITY),
+            aspect_ratio: Some(ratio),
+        }
+    }
+
+    #[test]
+    fn sized_box_keeps_its_aspect_ratio() {
+        let layout = aspect_ratio(Size2::zero(), 2.);
+        let mut children = vec![leaf(10., 10.)];
+
+        let size = layout.layout(BoxConstraints::loose(size2(200., 300.)), &mut children);
+        assert_eq!(size, size2(200., 100.));
+        assert_eq!(children[0].size(), size);
+
+        // too tall for the available height, so the width gives way
+        let size = layout.layout(BoxConstraints::loose(size2(200., 50.)), &mut children);
+        assert_eq!(size, size2(100., 50.));
+        assert_eq!(children[0].size(), size);
+    }
+
+    #[test]
+    fn sized_box_limits_win_over_its_aspect_ratio() {
+        let layout = aspect_ratio(size2(150., 0.), 2.);
+        let size = layout.layout(BoxConstraints::loose(size2(400., 50.)), &mut []);
+
+        assert_eq!(size, size2(150., 50.));
+    }
+
+    #[test]
+    fn unbounded_sized_box_takes_the_width_of_its_child() {
+        let layout = aspect_ratio(Size2::zero(), 2.);
+        let mut children = vec![leaf(80., 10.)];
+        let size = layout.layout(BoxConstraints::unbounded(), &mut children);
+
+        assert_eq!(size, size2(80., 40.));
+        assert_eq!(children[0].size(), size);
+    }
+
+    #[test]
+    fn sized_box_ignores_non_positive_aspect_ratios() {
+        let layout = aspect_ratio(Size2::zero(), 0.);
+        let size = layout.layout(
+            BoxConstraints::loose(size2(200., 300.)),
+            &mut [leaf(80., 10.)],
+        );
+
+        assert_eq!(size, size2(80., 10.));
+    }
+
+    fn wrap(cross_alignment: Align) -> WrapLayout {
+        WrapLayout {
+            margin: Sides2::zero(),
+            direction: WrapDirection::LeftToRight,
+            spacing: 10.,
+            run_spacing: 5.,
+            justify: JustifyContent::Start,
+            run_alignment: JustifyContent::Start,
+            cross_alignment,
+        }
+    }
+
+    /// Lays out `children` at their natural sizes, as `WrapLayout::runs` expects.
+    fn natural(mut children: Vec<ResolvedNode>) -> Vec<ResolvedNode> {
+        for child in &mut children {
+            child.layout(BoxConstraints::unbounded());
+        }
+        children
+    }
+
+    #[test]
+    fn justify_content_distributes_free_space() {
+        assert_eq!(JustifyContent::Start.distribute(12., 3), (0., 0.));
+        assert_eq!(JustifyContent::Center.distribute(12., 3), (6., 0.));
+        assert_eq!(JustifyContent::End.distribute(12., 3), (12., 0.));
+        assert_eq!(JustifyContent::SpaceBetween.distribute(12., 3), (0., 6.));
+        assert_eq!(JustifyContent::SpaceAround.distribute(12., 3), (2., 4.));
+        assert_eq!(JustifyContent::SpaceEvenly.distribute(12., 3), (3., 3.));
+    }
+
+    #[test]
+    fn justify_content_falls_back_to_start() {
+        assert_eq!(JustifyContent::SpaceBetween.distribute(12., 1), (0., 0.));
+        assert_eq!(JustifyContent::SpaceAround.distribute(-12., 3), (0., 0.));
+        assert_eq!(JustifyContent::SpaceEvenly.distribute(-12., 3), (0., 0.));
+        // overflowing content is still centered or end-aligned
+        assert_eq!(JustifyContent::Center.distribute(-12., 3), (-6., 0.));
+        assert_eq!(JustifyContent::End.distribute(-12., 3), (-12., 0.));
+    }
+
+    #[test]
+    fn flex_grows_items_by_their_factors() {
+        let layout = flex(
+            FlexDirection::Row,
+            vec![FlexItem::flex(1.), FlexItem::flex(2.)],
+        );
+        let mut children = vec![leaf(0., 10.), leaf(0., 10.)];
+        let size = layout.layout(BoxConstraints::loose(size2(300., 50.)), &mut children);
+
+        assert_eq!(size, size2(300., 10.));
+        assert_eq!(
+            children[0].rect(),
+            Rect::new(point2(0., 0.), size2(100., 10.))
+        );
+        assert_eq!(
+            children[1].rect(),
+            Rect::new(point2(100., 0.), size2(200., 10.))
+        );
+    }
+
+    #[test]
+    fn flex_factors_below_one_take_their_share() {
+        let layout = flex(FlexDirection::Row, vec![FlexItem::flex(0.5)]);
+        let mut children = vec![leaf(0., 10.)];
+        layout.layout(BoxConstraints::loose(size2(200., 50.)), &mut children);
+
+        assert_eq!(children[0].size().width, 100.);
+    }
+
+    #[test]
+    fn flex_shrinks_items_down_to_their_minimum() {
+        let layout = flex(
+            FlexDirection::Row,
+            vec![FlexItem::default(), FlexItem::default()],
+        );
+        let mut children = vec![
+            node(Leaf {
+                size: size2(50., 10.),
+                min_width: 50.,
+                baseline: None,
+            }),
+            leaf(100., 10.),
+        ];
+        layout.layout(BoxConstraints::loose(size2(100., 50.)), &mut children);
+
+        // the first item would shrink to a third of its width, but is frozen at its minimum and the second takes the rest
+        assert_eq!(
+            children[0].rect(),
+            Rect::new(point2(0., 0.), size2(50., 10.))
+        );
+        assert_eq!(
+            children[1].rect(),
+            Rect::new(point2(50., 0.), size2(50., 10.))
+        );
+    }
+
+    #[test]
+    fn flex_rows_fill_and_columns_wrap_their_content() {
+        let constraints = BoxConstraints::loose(size2(100., 300.));
+
+        let row = flex(FlexDirection::Row, vec![FlexItem::default(); 2]);
+        let size = row.layout(constraints, &mut [leaf(20., 30.), leaf(20., 40.)]);
+        assert_eq!(size, size2(100., 40.));
+
+        let column = flex(FlexDirection::Column, vec![FlexItem::default(); 2]);
+        let size = column.layout(constraints, &mut [leaf(20., 30.), leaf(20., 40.)]);
+        assert_eq!(size, size2(20., 70.));
+
+        let growing = flex(
+            FlexDirection::Column,
+            vec![FlexItem::default(), FlexItem::flex(1.)],
+        );
+        let size = growing.layout(constraints, &mut [leaf(20., 30.), leaf(20., 40.)]);
+        assert_eq!(size, size2(20., 300.));
+    }
+
+    #[test]
+    fn grid_places_explicit_items_first() {
+        let layout = grid(vec![
+            GridItem::default(),
+            GridItem::at(0, 0),
+            GridItem::default(),
+            GridItem::default(),
+        ]);
+
+        assert_eq!(
+            layout.place(2),
+            vec![(0, 1, 1, 1), (0, 0, 1, 1), (1, 0, 1, 1), (1, 1, 1, 1)]
+        );
+    }
+
+    #[test]
+    fn grid_clamps_spans_to_the_columns() {
+        let layout = grid(vec![
+            GridItem::default().span(1, 3),
+            GridItem {
+                row: Some(1),
+                ..Default::default()
+            },
+            GridItem::at(1, 5),
+        ]);
+
+        // the column is clamped as well, leaving the row-only item the other column
+        assert_eq!(
+            layout.place(2),
+            vec![(0, 0, 1, 2), (1, 0, 1, 1), (1, 1, 1, 1)]
+        );
+    }
+
+    #[test]
+    fn grid_fractions_share_the_space_left() {
+        let tracks = [
+            Track::Fixed(50.),
+            Track::Auto,
+            Track::Fraction(1.),
+            Track::Fraction(3.),
+        ];
+        let sizes = size_tracks(&tracks, &[(1, 1, 30.)], 10., 300.);
+
+        assert_eq!(sizes, vec![50., 30., 47.5, 142.5]);
+    }
+
+    #[test]
+    fn grid_spanning_items_grow_auto_tracks() {
+        let tracks = [Track::Fixed(20.), Track::Auto, Track::Auto];
+        // the spanning item comes first, but is only fitted once the single track item has been
+        let sizes = size_tracks(&tracks, &[(0, 3, 100.), (1, 1, 40.)], 5., f32::INFINITY);
+
+        assert_eq!(sizes, vec![20., 55., 15.]);
+    }
+
+    #[test]
+    fn grid_fractions_are_auto_when_unbounded() {
+        let tracks = [Track::Fraction(1.), Track::Fraction(1.)];
+        let sizes = size_tracks(&tracks, &[(0, 1, 10.), (1, 1, 20.)], 0., f32::INFINITY);
+
+        assert_eq!(sizes, vec![10., 20.]);
+    }
+
+    #[test]
+    fn wrap_breaks_runs_at_the_width() {
+        let children = natural(vec![
+            leaf(40., 10.),
+            leaf(40., 20.),
+            leaf(40., 10.),
+            leaf(150., 30.),
+            leaf(20., 10.),
+        ]);
+        let runs = wrap(Align::Begin).runs(100., &children);
+
+        // children wider than a run get one to themselves
+        let runs = runs
+            .iter()
+            .map(|run| (run.children.clone(), run.width, run.height))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            runs,
+            vec![
+                (0..2, 90., 20.),
+                (2..3, 40., 10.),
+                (3..4, 150., 30.),
+                (4..5, 20., 10.),
+            ]
+        );
+    }
+
+    #[test]
+    fn wrap_runs_fit_their_baselines() {
+        let children = natural(vec![
+            node(Leaf {
+                size: size2(20., 30.),
+                min_width: 0.,
+                baseline: Some(10.),
+            }),
+            node(Leaf {
+                size: size2(20., 20.),
+                min_width: 0.,
+                baseline: Some(18.),
+            }),
+        ]);
+        let runs = wrap(Align::Baseline).runs(100., &children);
+
+        // the second child reaches 8 above the first's baseline, and the first 20 below it
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].baseline, 18.);
+        assert_eq!(runs[0].height, 38.);
+    }
+
+    #[test]
+    fn wrap_places_runs_right_to_left() {
+        let layout = WrapLayout {
+            direction: WrapDirection::RightToLeft,
+            ..wrap(Align::Begin)
+        };
+        let mut children = vec![leaf(40., 10.), leaf(40., 20.), leaf(40., 10.)];
+        let size = layout.layout(BoxConstraints::loose(size2(100., 100.)), &mut children);
+
+        assert_eq!(size, size2(100., 35.));
+        assert_eq!(children[0].position(), point2(60., 0.));
+        assert_eq!(children[1].position(), point2(10., 0.));
+        assert_eq!(children[2].position(), point2(60., 25.));
+    }
+}
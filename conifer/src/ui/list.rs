@@ -0,0 +1,261 @@
+use cape::{
+    cx::{Cx, Handle, State},
+    node::{capture, clip, interact, keyed, Interaction, IntoNode, Layout, Node},
+    point2, size2, Rect, Size2,
+};
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc};
+
+struct VirtualListLayout {
+    width: Option<f32>,
+    height: f32,
+    start: f32,
+    row_height: Option<f32>,
+    /// Natural heights of the rows, recorded when they aren't fixed.
+    measured: Rc<RefCell<Vec<f32>>>,
+}
+
+impl Layout for VirtualListLayout {
+    fn size(&self, sizes: &[Size2]) -> Size2 {
+        let width = self.width.unwrap_or_else(|| {
+            sizes
+                .iter()
+                .map(|size| size.width)
+                .fold(0., |a: f32, b| a.max(b))
+        });
+        size2(width, self.height)
+    }
+
+    fn position(&self, rect: Rect, sizes: &[Size2]) -> Vec<Rect> {
+        if self.row_height.is_none() {
+            *self.measured.borrow_mut() = sizes.iter().map(|size| size.height).collect();
+        }
+
+        let mut y = rect.origin.y + self.start;
+        let mut out = Vec::new();
+
+        for size in sizes {
+            let height = self.row_height.unwrap_or(size.height);
+            out.push(Rect::new(
+                point2(rect.origin.x, y),
+                size2(rect.size.width, height),
+            ));
+            y += height;
+        }
+
+        out
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RowHeight {
+    /// Every row is exactly this tall.
+    Fixed(f32),
+    /// Rows are roughly this tall; rows are laid out at their own height, which is remembered once they have been built, while the estimate is used for rows which haven't been.
+    Estimated(f32),
+}
+
+impl Default for RowHeight {
+    fn default() -> Self {
+        RowHeight::Fixed(20.)
+    }
+}
+
+impl RowHeight {
+    fn extent(&self) -> f32 {
+        match *self {
+            RowHeight::Fixed(height) | RowHeight::Estimated(height) => height.max(1.),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ListScroll {
+    pub offset: f32,
+    target: Option<usize>,
+    /// Measured heights of rows, by index, for `RowHeight::Estimated`.
+    heights: BTreeMap<usize, f32>,
+}
+
+impl ListScroll {
+    /// Scrolls the list so that the row at `index` is at the top of the viewport.
+    pub fn scroll_to(&mut self, index: usize) {
+        self.target = Some(index);
+    }
+}
+
+/// Vertical extents of the rows of a list, where rows which haven't been measured are `estimate` tall.
+struct RowExtents<'a> {
+    estimate: f32,
+    measured: &'a BTreeMap<usize, f32>,
+}
+
+impl RowExtents<'_> {
+    /// Returns the offset of the top of the row at `index` from the top of the list.
+    fn offset_of(&self, index: usize) -> f32 {
+        self.estimate * index as f32
+            + self
+                .measured
+                .range(..index)
+                .map(|(_, height)| height - self.estimate)
+                .sum::<f32>()
+    }
+
+    /// Returns the index of the row at `offset` from the top of the list, which is `len` or more past the last row.
+    fn row_at(&self, offset: f32, len: usize) -> usize {
+        let mut top = 0.;
+        let mut row = 0;
+        for (&index, &height) in self.measured.range(..len) {
+            // every row between the previous measured row and this one is estimated
+            let estimated = self.estimate * (index - row) as f32;
+            if offset < top + estimated {
+                break;
+            }
+            top += estimated;
+            if offset < top + height {
+                return index;
+            }
+            top += height;
+            row = index + 1;
+        }
+        row + ((offset - top).max(0.) / self.estimate) as usize
+    }
+}
+
+type RowBuilder<'a> = Box<dyn FnMut(&mut Cx, usize) -> Node + 'a>;
+
+pub struct VirtualList<'a> {
+    cx: &'a mut Cx,
+    len: usize,
+    builder: RowBuilder<'a>,
+    row_height: RowHeight,
+    width: Option<f32>,
+    height: f32,
+    overscan: usize,
+    scroll: Handle<ListScroll, State>,
+}
+
+impl<'a> IntoNode for VirtualList<'a> {
+    #[cape::ui]
+    fn into_node(mut self) -> Node {
+        let scroll = self.scroll;
+        let (len, height) = (self.len, self.height);
+
+        let state = self.cx.at(scroll);
+        let extents = RowExtents {
+            estimate: self.row_height.extent(),
+            measured: &state.heights,
+        };
+        let max_offset = (extents.offset_of(len) - height).max(0.);
+        if let Some(index) = state.target.take() {
+            state.offset = extents.offset_of(index.min(len));
+        }
+        state.offset = state.offset.clamp(0., max_offset);
+        let offset = state.offset;
+
+        let first = extents
+            .row_at(offset, len)
+            .saturating_sub(self.overscan)
+            .min(len);
+        let last = (extents.row_at(offset + height, len) + 1 + self.overscan).min(len);
+        let start = extents.offset_of(first) - offset;
+
+        let mut rows = Vec::with_capacity(last - first);
+        for i in first..last {
+            let (builder, cx) = (&mut self.builder, &mut *self.cx);
+            rows.push(keyed(&i, || builder(cx, i)));
+        }
+
+        let measured = Rc::new(RefCell::new(Vec::new()));
+        let list = Node::Layout {
+            layout: Rc::new(VirtualListLayout {
+                width: self.width,
+                height,
+                start,
+                row_height: match self.row_height {
+                    RowHeight::Fixed(height) => Some(height),
+                    RowHeight::Estimated(_) => None,
+                },
+                measured: Rc::clone(&measured),
+            }),
+            children: rows,
+        };
+
+        // rows which turn out taller or shorter than estimated move everything below them, so redraw with their real height
+        let list = capture(list, move |cx, _| {
+            let state = cx.at(scroll);
+            let mut changed = false;
+            for (i, &height) in measured.borrow().iter().enumerate() {
+                changed |= state.heights.insert(first + i, height) != Some(height);
+            }
+            if changed {
+                cx.request_redraw();
+            }
+        });
+
+        interact(
+            clip([0.; 4], list),
+            move |cx, event| {
+                if let Interaction::Scroll { delta, .. } = event {
+                    let state = cx.at(scroll);
+                    let offset = (state.offset - delta.y).clamp(0., max_offset);
+                    // at either end the scroll is left to outer scrollable containers
+                    if offset != state.offset {
+                        state.offset = offset;
+                        cx.stop_propagation();
+                    }
+                }
+            },
+            false,
+        )
+    }
+}
+
+impl<'a> VirtualList<'a> {
+    /// Creates a list of `len` rows, where `builder` is only invoked for the rows which are visible.
+    #[cape::ui]
+    pub fn new<N: IntoNode>(
+        cx: &'a mut Cx,
+        len: usize,
+        mut builder: impl FnMut(&mut Cx, usize) -> N + 'a,
+    ) -> Self {
+        let scroll = cx.state(ListScroll::default);
+
+        VirtualList {
+            cx,
+            len,
+            builder: Box::new(move |cx, i| builder(cx, i).into_node()),
+            row_height: Default::default(),
+            width: None,
+            height: 200.,
+            overscan: 2,
+            scroll,
+        }
+    }
+
+    pub fn row_height(mut self, row_height: RowHeight) -> Self {
+        self.row_height = row_height;
+        self
+    }
+
+    pub fn width(mut self, width: impl Into<Option<f32>>) -> Self {
+        self.width = width.into();
+        self
+    }
+
+    pub fn height(mut self, height: f32) -> Self {
+        self.height = height;
+        self
+    }
+
+    /// Number of rows built beyond either edge of the viewport.
+    pub fn overscan(mut self, overscan: usize) -> Self {
+        self.overscan = overscan;
+        self
+    }
+
+    /// Uses an external scroll state, e.g. to call `ListScroll::scroll_to`.
+    pub fn state(mut self, scroll: Handle<ListScroll, State>) -> Self {
+        self.scroll = scroll;
+        self
+    }
+}
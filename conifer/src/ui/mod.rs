@@ -1,8 +1,12 @@
 mod button;
 mod combo_box;
 mod layout;
+mod list;
 mod misc;
+mod responsive;
 mod slider;
 mod text_box;
 
-pub use {button::*, combo_box::*, layout::*, misc::*, slider::*, text_box::*};
+pub use {
+    button::*, combo_box::*, layout::*, list::*, misc::*, responsive::*, slider::*, text_box::*,
+};
@@ -0,0 +1,184 @@
+use cape::{
+    cx::Cx,
+    node::{IntoNode, Node},
+};
+
+/// Named ranges of available width, from narrowest to widest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Breakpoint {
+    Compact,
+    Medium,
+    Expanded,
+}
+
+/// Widths at which each `Breakpoint` starts; anything narrower than `medium` is `Compact`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Breakpoints {
+    pub medium: f32,
+    pub expanded: f32,
+}
+
+impl Default for Breakpoints {
+    fn default() -> Self {
+        Breakpoints {
+            medium: 600.,
+            expanded: 840.,
+        }
+    }
+}
+
+impl Breakpoints {
+    pub fn classify(&self, width: f32) -> Breakpoint {
+        if width >= self.expanded {
+            Breakpoint::Expanded
+        } else if width >= self.medium {
+            Breakpoint::Medium
+        } else {
+            Breakpoint::Compact
+        }
+    }
+}
+
+/// Where `Responsive` measures its width from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResponsiveWidth {
+    /// The maximum width it is laid out within; unbounded widths are `Expanded`.
+    Available,
+    /// The width of the window.
+    Viewport,
+}
+
+impl Default for ResponsiveWidth {
+    fn default() -> Self {
+        ResponsiveWidth::Available
+    }
+}
+
+type ResponsiveBuilder<'a> = Box<dyn FnOnce(&mut Cx) -> Node + 'a>;
+
+/// Picks between nodes depending on the current `Breakpoint`.
+///
+/// If there is no node for the current breakpoint, the node for the nearest narrower breakpoint is used, or failing that, the narrowest node.
+pub struct Responsive<'a> {
+    cx: &'a mut Cx,
+    breakpoints: Breakpoints,
+    width: ResponsiveWidth,
+    builders: Vec<(Breakpoint, ResponsiveBuilder<'a>)>,
+}
+
+impl<'a> IntoNode for Responsive<'a> {
+    #[cape::ui]
+    fn into_node(self) -> Node {
+        let Responsive {
+            cx,
+            breakpoints,
+            width,
+            mut builders,
+        } = self;
+
+        builders.sort_by_key(|(breakpoint, _)| *breakpoint);
+
+        let mut pick = move |cx: &mut Cx, width: f32| {
+            let current = breakpoints.classify(width);
+            let index = builders
+                .iter()
+                .rposition(|(breakpoint, _)| *breakpoint <= current)
+                .unwrap_or(0);
+            if builders.is_empty() {
+                Node::Null
+            } else {
+                (builders.swap_remove(index).1)(cx)
+            }
+        };
+
+        match width {
+            ResponsiveWidth::Available => {
+                cx.layout_builder(|cx, constraints| pick(cx, constraints.max.width))
+            }
+            ResponsiveWidth::Viewport => {
+                let width = cx.viewport().width;
+                pick(cx, width)
+            }
+        }
+    }
+}
+
+impl<'a> Responsive<'a> {
+    pub fn new(cx: &'a mut Cx) -> Self {
+        Responsive {
+            cx,
+            breakpoints: Default::default(),
+            width: Default::default(),
+            builders: Vec::new(),
+        }
+    }
+
+    /// Uses `builder` from `breakpoint` upwards, until a wider breakpoint has a node of its own.
+    pub fn at<N: IntoNode>(
+        mut self,
+        breakpoint: Breakpoint,
+        builder: impl FnOnce(&mut Cx) -> N + 'a,
+    ) -> Self {
+        self.builders
+            .retain(|(existing, _)| *existing != breakpoint);
+        self.builders
+            .push((breakpoint, Box::new(move |cx| builder(cx).into_node())));
+        self
+    }
+
+    pub fn compact<N: IntoNode>(self, builder: impl FnOnce(&mut Cx) -> N + 'a) -> Self {
+        self.at(Breakpoint::Compact, builder)
+    }
+
+    pub fn medium<N: IntoNode>(self, builder: impl FnOnce(&mut Cx) -> N + 'a) -> Self {
+        self.at(Breakpoint::Medium, builder)
+    }
+
+    pub fn expanded<N: IntoNode>(self, builder: impl FnOnce(&mut Cx) -> N + 'a) -> Self {
+        self.at(Breakpoint::Expanded, builder)
+    }
+
+    pub fn breakpoints(mut self, breakpoints: Breakpoints) -> Self {
+        self.breakpoints = breakpoints;
+        self
+    }
+
+    pub fn width(mut self, width: ResponsiveWidth) -> Self {
+        self.width = width;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn breakpoints_start_at_their_width() {
+        let breakpoints = Breakpoints::default();
+        assert_eq!(breakpoints.classify(0.), Breakpoint::Compact);
+        assert_eq!(breakpoints.classify(599.9), Breakpoint::Compact);
+        assert_eq!(breakpoints.classify(600.), Breakpoint::Medium);
+        assert_eq!(breakpoints.classify(839.9), Breakpoint::Medium);
+        assert_eq!(breakpoints.classify(840.), Breakpoint::Expanded);
+    }
+
+    #[test]
+    fn unbounded_widths_are_expanded() {
+        assert_eq!(
+            Breakpoints::default().classify(f32::INFINITY),
+            Breakpoint::Expanded
+        );
+    }
+
+    #[test]
+    fn custom_breakpoints_classify_by_their_own_widths() {
+        let breakpoints = Breakpoints {
+            medium: 300.,
+            expanded: 500.,
+        };
+        assert_eq!(breakpoints.classify(299.), Breakpoint::Compact);
+        assert_eq!(breakpoints.classify(300.), Breakpoint::Medium);
+        assert_eq!(breakpoints.classify(500.), Breakpoint::Expanded);
+    }
+}